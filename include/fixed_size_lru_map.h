@@ -0,0 +1,56 @@
+/*
+ * C interface of fixed_size_lru_map, built with the `ffi` feature:
+ *
+ *     cargo build --release --features ffi
+ *
+ * then link against target/release/libfixed_size_lru_map.a (static) or
+ * libfixed_size_lru_map.so (shared). Keep in sync with src/ffi.rs.
+ */
+#ifndef FIXED_SIZE_LRU_MAP_H
+#define FIXED_SIZE_LRU_MAP_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A map of byte string keys and values, safe to use from several threads. */
+typedef struct LruMap LruMap;
+
+/* A value read from a map, readable until freed even if evicted meanwhile. */
+typedef struct LruValue LruValue;
+
+/* Creates a map holding up to `capacity` entries, to be freed with lru_map_free. */
+LruMap *lru_map_create(size_t capacity);
+
+/* Frees a map, the values still held stay valid. Null is ignored. */
+void lru_map_free(LruMap *map);
+
+/* Returns the value of a key, or null when missing, to be freed with lru_value_free. */
+LruValue *lru_map_get(const LruMap *map, const uint8_t *key, size_t key_len);
+
+/* Copies and inserts a value, replacing the previous one of the key. */
+void lru_map_insert(const LruMap *map, const uint8_t *key, size_t key_len,
+                    const uint8_t *value, size_t value_len);
+
+/* Returns the number of entries. */
+size_t lru_map_len(const LruMap *map);
+
+/* Removes a key, returning whether it was present. */
+bool lru_map_remove(const LruMap *map, const uint8_t *key, size_t key_len);
+
+/* Returns the bytes of a value and writes their length to `len`. The bytes
+ * are valid until the value is freed. */
+const uint8_t *lru_value_data(const LruValue *value, size_t *len);
+
+/* Frees a value returned by lru_map_get. Null is ignored. */
+void lru_value_free(LruValue *value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FIXED_SIZE_LRU_MAP_H */
@@ -0,0 +1,84 @@
+//! Renormalization of the entry ages.
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering::{Relaxed, SeqCst},
+};
+
+/// Once the age counter reaches this value, the ages are rebased on the next insert.
+///
+/// Half of the range is left so the reads happening between two inserts can
+/// never wrap the counter.
+pub(crate) const REBASE_THRESHOLD: u64 = u64::MAX / 2;
+
+/// Renumbers `ages` from zero, keeping their relative order, and restarts
+/// `counter` right after the most recent one.
+///
+/// Must be called while no entry can be added or removed, i.e. under the write
+/// lock, but the reads may [`touch`] the ages meanwhile.
+pub(crate) fn rebase<'a, I>(counter: &AtomicU64, ages: I)
+where
+    I: IntoIterator<Item = &'a AtomicU64>,
+{
+    let mut ages = ages.into_iter().collect::<Vec<_>>();
+    ages.sort_unstable_by_key(|age| age.load(Relaxed));
+
+    // restarted before renumbering: a touch storing an age from before the
+    // rebase either sees the new counter or is overwritten below.
+    counter.store(ages.len() as u64, SeqCst);
+
+    for (i, age) in ages.iter().enumerate() {
+        age.store(i as u64, SeqCst);
+    }
+}
+
+/// Sets `age` to the next value of `counter`, marking the entry as the most
+/// recently used one, even during a concurrent [`rebase`].
+pub(crate) fn touch(counter: &AtomicU64, age: &AtomicU64) {
+    let mut next = counter.fetch_add(1, Relaxed);
+
+    loop {
+        age.store(next, SeqCst);
+
+        // the counter only goes back on a rebase, which our age is older than.
+        if counter.load(SeqCst) > next {
+            return;
+        }
+
+        next = counter.fetch_add(1, Relaxed);
+    }
+}
+
+#[test]
+fn test_rebase() {
+    let counter = AtomicU64::new(REBASE_THRESHOLD);
+    let ages = [
+        AtomicU64::new(REBASE_THRESHOLD - 1),
+        AtomicU64::new(7),
+        AtomicU64::new(REBASE_THRESHOLD - 10),
+    ];
+
+    rebase(&counter, &ages);
+
+    assert_eq!(3, counter.load(Relaxed));
+    assert_eq!(
+        vec![2, 0, 1],
+        ages.iter().map(|a| a.load(Relaxed)).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_touch_during_rebase() {
+    let counter = AtomicU64::new(REBASE_THRESHOLD);
+    let ages = [AtomicU64::new(1), AtomicU64::new(2)];
+
+    // a touch takes its age, then a rebase runs before it is stored.
+    let stale = counter.fetch_add(1, Relaxed);
+    rebase(&counter, &ages);
+    ages[0].store(stale, SeqCst);
+    assert!(counter.load(SeqCst) <= stale);
+
+    // the next touch sees the restarted counter.
+    touch(&counter, &ages[0]);
+    assert_eq!(2, ages[0].load(Relaxed));
+    assert_eq!(3, counter.load(Relaxed));
+}
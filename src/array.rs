@@ -0,0 +1,279 @@
+//! Fixed size lru maps whose capacity is a constant, stored in an inline array.
+use crate::{age, lock::Lock};
+use parking_lot::{MappedRwLockReadGuard, RwLockReadGuard};
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// A borrowed value of a [`SmallLruMap`].
+///
+/// The map is read-locked for as long as this reference lives, so it should be
+/// dropped as soon as possible.
+pub type SmallRef<'a, V> = MappedRwLockReadGuard<'a, V>;
+
+/// A lru map of at most `N` entries, stored inline without any heap allocation.
+///
+/// The keys are found by a linear search, which beats hashing for the handful
+/// of entries such a map is meant for. Lookups take `&self`, so the map can be
+/// read from several threads while the mutations take `&mut self`.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::ArrayLruMap;
+///
+/// let mut map = ArrayLruMap::<_, _, 2>::new();
+/// map.insert("a", 1);
+/// map.insert("b", 2);
+/// assert_eq!(Some(&1), map.get(&"a"));
+///
+/// // "b" is the least recently used.
+/// assert_eq!(Some(2), map.insert("c", 3));
+/// ```
+pub struct ArrayLruMap<K, V, const N: usize> {
+    age: AtomicU64,
+    len: usize,
+    /// The occupied slots come first, in no particular order.
+    slots: [Option<Slot<K, V>>; N],
+}
+
+struct Slot<K, V> {
+    age: AtomicU64,
+    key: K,
+    value: V,
+}
+
+impl<K, V, const N: usize> ArrayLruMap<K, V, N>
+where
+    K: Eq,
+{
+    pub const fn new() -> Self {
+        ArrayLruMap {
+            age: AtomicU64::new(0),
+            len: 0,
+            slots: [const { None }; N],
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.position(key).is_some()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let slot = self.slot(self.position(key)?);
+        slot.age.store(self.age.fetch_add(1, Relaxed), Relaxed);
+        Some(&slot.value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.position(key)?;
+        let age = self.age.fetch_add(1, Relaxed);
+        let slot = self.slots[index].as_mut().expect("occupied slot");
+        *slot.age.get_mut() = age;
+        Some(&mut slot.value)
+    }
+
+    /// Inserts a value, returning the value it replaced or the one evicted to make room.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if N == 0 {
+            return Some(value);
+        }
+
+        let mut age = self.age.fetch_add(1, Relaxed);
+
+        if age >= age::REBASE_THRESHOLD {
+            age::rebase(&self.age, self.occupied().map(|slot| &slot.age));
+            age = self.age.fetch_add(1, Relaxed);
+        }
+
+        let slot = Slot {
+            age: AtomicU64::new(age),
+            key,
+            value,
+        };
+
+        let index = match self.position(&slot.key) {
+            Some(index) => index,
+            None if self.len < N => {
+                self.len += 1;
+                self.len - 1
+            }
+            None => self
+                .occupied()
+                .enumerate()
+                .min_by_key(|(_, slot)| slot.age.load(Relaxed))
+                .map(|(i, _)| i)
+                .expect("full map"),
+        };
+
+        self.slots[index].replace(slot).map(|old| old.value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entries, in no particular order, without touching their recency.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.occupied().map(|slot| (&slot.key, &slot.value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.position(key)?;
+        self.len -= 1;
+        self.slots.swap(index, self.len);
+        self.slots[self.len].take().map(|slot| slot.value)
+    }
+
+    fn occupied(&self) -> impl Iterator<Item = &Slot<K, V>> {
+        self.slots[..self.len].iter().flatten()
+    }
+
+    fn position(&self, key: &K) -> Option<usize> {
+        self.occupied().position(|slot| slot.key == *key)
+    }
+
+    fn slot(&self, index: usize) -> &Slot<K, V> {
+        self.slots[index].as_ref().expect("occupied slot")
+    }
+}
+
+impl<K, V, const N: usize> Default for ArrayLruMap<K, V, N>
+where
+    K: Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A thread safe lru map of at most `N` entries, for tiny caches.
+///
+/// Unlike [`FixedSizeLruMap`](crate::FixedSizeLruMap), there is no hash table
+/// nor any allocation per entry: the entries live in an [`ArrayLruMap`] behind
+/// the lock and are found by a linear search, which is faster up to a few dozen
+/// entries. The values are borrowed through a [`SmallRef`] and, like in a
+/// [`SlabLruMap`](crate::SlabLruMap), moved out when removed or evicted.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::SmallLruMap;
+///
+/// let map = SmallLruMap::<_, _, 8>::new();
+/// map.insert("a", 1);
+/// assert_eq!(1, *map.get(&"a").unwrap());
+/// ```
+pub struct SmallLruMap<K, V, const N: usize> {
+    inner: Lock<ArrayLruMap<K, V, N>>,
+}
+
+impl<K, V, const N: usize> SmallLruMap<K, V, N>
+where
+    K: Eq,
+{
+    pub fn new() -> Self {
+        SmallLruMap {
+            inner: Lock::new(ArrayLruMap::new()),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.read().contains_key(key)
+    }
+
+    pub fn get(&self, key: &K) -> Option<SmallRef<'_, V>> {
+        RwLockReadGuard::try_map(self.inner.read(), |map| map.get(key)).ok()
+    }
+
+    /// Calls `f` with the value of `key` without keeping the map locked afterward.
+    pub fn get_with<F, R>(&self, key: &K, f: F) -> Option<R>
+    where
+        F: FnOnce(&V) -> R,
+    {
+        self.inner.read().get(key).map(f)
+    }
+
+    /// Inserts a value, returning the value it replaced or the one evicted to make room.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.inner.write().insert(key, value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().len()
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.inner.write().remove(key)
+    }
+}
+
+impl<K, V, const N: usize> Default for SmallLruMap<K, V, N>
+where
+    K: Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_array_lru_map() {
+    let mut map = ArrayLruMap::<_, _, 3>::new();
+    map.insert(1, "a");
+    map.insert(2, "b");
+    map.insert(3, "c");
+
+    assert_eq!(Some(&"a"), map.get(&1));
+    assert_eq!(Some("c"), map.insert(3, "C"));
+    assert_eq!(3, map.len());
+
+    // 2 is the least recently used.
+    assert_eq!(Some("b"), map.insert(4, "d"));
+    assert!(!map.contains_key(&2));
+
+    *map.get_mut(&4).unwrap() = "D";
+    assert_eq!(Some("a"), map.remove(&1));
+
+    let mut entries = map.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>();
+    entries.sort_unstable();
+    assert_eq!(vec![(3, "C"), (4, "D")], entries);
+
+    let mut empty = ArrayLruMap::<_, _, 0>::new();
+    assert_eq!(Some(1), empty.insert("a", 1));
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_small_lru_map() {
+    let map = SmallLruMap::<_, _, 2>::new();
+    map.insert("a", 1);
+    map.insert("b", 2);
+
+    std::thread::scope(|s| {
+        s.spawn(|| assert_eq!(1, *map.get(&"a").unwrap()));
+    });
+
+    // "b" is the least recently used.
+    assert_eq!(Some(2), map.insert("c", 3));
+    assert_eq!(Some(4), map.get_with(&"c", |v| v + 1));
+    assert_eq!(Some(1), map.remove(&"a"));
+    assert_eq!(1, map.len());
+}
@@ -0,0 +1,251 @@
+use crate::{
+    events::{InsertHook, RemovalListener, UpdateHook},
+    pool::EntryPool,
+    semaphore::Semaphore,
+    weight::Weigher,
+    FixedSizeLruMap, Instrument, MapGuard, RemovalCause,
+};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+    time::Duration,
+};
+
+/// Configures and creates a [`FixedSizeLruMap`].
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::FixedSizeLruMap;
+///
+/// let map = FixedSizeLruMap::builder(100).entry_pool(16).build();
+/// map.insert("a", 1);
+/// assert_eq!(1, *map.get(&"a").unwrap());
+/// ```
+pub struct FixedSizeLruMapBuilder<K, V, S = RandomState> {
+    capacity: usize,
+    entry_pool: usize,
+    hash_builder: S,
+    #[cfg(feature = "stats")]
+    hit_ratio_window: Option<Duration>,
+    instrument: Option<Box<dyn Instrument<K>>>,
+    max_concurrent_loads: Option<usize>,
+    max_weight: Option<u64>,
+    negative_ttl: Option<Duration>,
+    on_insert: Option<InsertHook<K, V>>,
+    on_update: Option<UpdateHook<K, V>>,
+    refresh_after: Option<Duration>,
+    removal_listener: Option<RemovalListener<K, V>>,
+    timestamps: bool,
+    #[cfg(feature = "trace")]
+    trace: usize,
+    weigher: Option<Weigher<K, V>>,
+    _kv: PhantomData<fn(K, V)>,
+}
+
+impl<K, V> FixedSizeLruMapBuilder<K, V> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        FixedSizeLruMapBuilder {
+            capacity,
+            entry_pool: 0,
+            hash_builder: Default::default(),
+            #[cfg(feature = "stats")]
+            hit_ratio_window: None,
+            instrument: None,
+            max_concurrent_loads: None,
+            max_weight: None,
+            negative_ttl: None,
+            on_insert: None,
+            on_update: None,
+            refresh_after: None,
+            removal_listener: None,
+            timestamps: false,
+            #[cfg(feature = "trace")]
+            trace: 0,
+            weigher: None,
+            _kv: PhantomData,
+        }
+    }
+}
+
+impl<K, V, S> FixedSizeLruMapBuilder<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Keeps up to `size` evicted or removed entries around so their allocation
+    /// can be reused by the next inserts, relieving the allocator in high-churn caches.
+    pub fn entry_pool(mut self, size: usize) -> Self {
+        self.entry_pool = size;
+        self
+    }
+
+    pub fn hasher<S2>(self, hash_builder: S2) -> FixedSizeLruMapBuilder<K, V, S2> {
+        FixedSizeLruMapBuilder {
+            capacity: self.capacity,
+            entry_pool: self.entry_pool,
+            hash_builder,
+            #[cfg(feature = "stats")]
+            hit_ratio_window: self.hit_ratio_window,
+            instrument: self.instrument,
+            max_concurrent_loads: self.max_concurrent_loads,
+            max_weight: self.max_weight,
+            negative_ttl: self.negative_ttl,
+            on_insert: self.on_insert,
+            on_update: self.on_update,
+            refresh_after: self.refresh_after,
+            removal_listener: self.removal_listener,
+            timestamps: self.timestamps,
+            #[cfg(feature = "trace")]
+            trace: self.trace,
+            weigher: self.weigher,
+            _kv: PhantomData,
+        }
+    }
+
+    /// Sets the period covered by [`windowed_hit_ratio`](FixedSizeLruMap::windowed_hit_ratio),
+    /// one minute by default.
+    #[cfg(feature = "stats")]
+    pub fn hit_ratio_window(mut self, window: Duration) -> Self {
+        self.hit_ratio_window = Some(window);
+        self
+    }
+
+    /// Reports the loads and evictions of the map to `instrument`.
+    pub fn instrument<I>(mut self, instrument: I) -> Self
+    where
+        I: Instrument<K> + 'static,
+    {
+        self.instrument = Some(Box::new(instrument));
+        self
+    }
+
+    /// Caps how many initializers may run at the same time across all keys, so
+    /// a burst of cold misses cannot exhaust the resources of the backing store.
+    pub fn max_concurrent_loads(mut self, max: usize) -> Self {
+        self.max_concurrent_loads = Some(max);
+        self
+    }
+
+    /// Evicts the least recently used entries until the sum of their weights,
+    /// given by the [`weigher`](Self::weigher), is at most `max`.
+    ///
+    /// The capacity still bounds the number of entries and the table is sized
+    /// for it, so it should be a realistic upper bound rather than `usize::MAX`.
+    /// The entry just inserted is never evicted for its weight, even when it
+    /// weighs more than `max` on its own.
+    pub fn max_weight(mut self, max: u64) -> Self {
+        self.max_weight = Some(max);
+        self
+    }
+
+    /// Forgets after `ttl` that a key has no value, see
+    /// [`get_or_maybe_init`](FixedSizeLruMap::get_or_maybe_init). Without it, a
+    /// missing key stays missing until a value is inserted or its tombstone is evicted.
+    pub fn negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = Some(ttl);
+        self
+    }
+
+    /// Calls `hook` with every value inserted for a new key, e.g. to maintain
+    /// a derived index alongside the map.
+    ///
+    /// The hook runs on the inserting thread once the map is unlocked, it may use the map.
+    pub fn on_insert<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&K, &MapGuard<V>) + Send + Sync + 'static,
+    {
+        self.on_insert = Some(Box::new(hook));
+        self
+    }
+
+    /// Calls `hook` with the old and the new value every time the value of a key is replaced.
+    ///
+    /// The hook runs on the inserting thread once the map is unlocked, it may use the map.
+    pub fn on_update<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&K, &MapGuard<V>, &MapGuard<V>) + Send + Sync + 'static,
+    {
+        self.on_update = Some(Box::new(hook));
+        self
+    }
+
+    /// Values loaded more than `after` ago are reloaded in the background when
+    /// accessed through [`get_or_refresh`](FixedSizeLruMap::get_or_refresh).
+    ///
+    /// Turns on the [`timestamps`](Self::timestamps).
+    pub fn refresh_after(mut self, after: Duration) -> Self {
+        self.refresh_after = Some(after);
+        self
+    }
+
+    /// Calls `listener` with every entry leaving the map and the reason why, so
+    /// the resources tied to the values can be released promptly.
+    ///
+    /// The listener runs on the thread removing the entry once the map is unlocked,
+    /// it may use the map.
+    pub fn removal_listener<F>(mut self, listener: F) -> Self
+    where
+        F: Fn(K, MapGuard<V>, RemovalCause) + Send + Sync + 'static,
+    {
+        self.removal_listener = Some(Box::new(listener));
+        self
+    }
+
+    /// Records the last `capacity` accesses, see [`trace`](FixedSizeLruMap::trace).
+    #[cfg(feature = "trace")]
+    pub fn trace(mut self, capacity: usize) -> Self {
+        self.trace = capacity;
+        self
+    }
+
+    /// Records when each entry was inserted and last read, see [`MapGuard::info`].
+    ///
+    /// Off by default, so the map never reads the clock on its own.
+    pub fn timestamps(mut self, enabled: bool) -> Self {
+        self.timestamps = enabled;
+        self
+    }
+
+    /// Weighs every entry with `weigher`, see [`weighted_size`](FixedSizeLruMap::weighted_size).
+    ///
+    /// The weigher must always return the same weight for the same entry.
+    pub fn weigher<F>(mut self, weigher: F) -> Self
+    where
+        F: Fn(&K, &V) -> u64 + Send + Sync + 'static,
+    {
+        self.weigher = Some(Box::new(weigher));
+        self
+    }
+
+    pub fn build(self) -> FixedSizeLruMap<K, V, S> {
+        let mut map = FixedSizeLruMap::with_capacity_and_hasher(self.capacity, self.hash_builder);
+
+        if self.entry_pool > 0 {
+            map.pool = Some(EntryPool::new(self.entry_pool));
+        }
+
+        map.insert_hook = self.on_insert;
+        map.instrument = self.instrument;
+        map.loaders = self.max_concurrent_loads.map(Semaphore::new);
+        map.weights.max = self.max_weight;
+        map.negatives.ttl = self.negative_ttl;
+        map.refresh_after = self.refresh_after;
+        map.removal_listener = self.removal_listener;
+        map.timestamps = self.timestamps || self.refresh_after.is_some();
+        map.update_hook = self.on_update;
+        map.weights.weigher = self.weigher;
+
+        #[cfg(feature = "trace")]
+        if self.trace > 0 {
+            map.trace = crate::trace::TraceRecorder::new(self.trace);
+        }
+
+        #[cfg(feature = "stats")]
+        if let Some(window) = self.hit_ratio_window {
+            map.stats.set_window(window);
+        }
+
+        map
+    }
+}
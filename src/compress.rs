@@ -0,0 +1,216 @@
+//! Values kept compressed in memory, for caches bound by memory rather than entries.
+use crate::persist::Persist;
+use std::{
+    fmt::{self, Debug, Formatter},
+    io::{self, Error, ErrorKind},
+    marker::PhantomData,
+};
+
+/// Shortest repetition worth a back reference.
+const MIN_MATCH: usize = 4;
+
+const TABLE_BITS: u32 = 12;
+
+/// A value stored compressed, decompressed on every access.
+///
+/// The value is encoded with [`Persist`] then compressed with a small LZ77
+/// scheme, which pays off for repetitive data such as text or serialized records.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::{Compressed, FixedSizeLruMap};
+///
+/// let map = FixedSizeLruMap::with_capacity(10);
+/// let page = "<p>hello</p>".repeat(100);
+/// map.insert(1, Compressed::new(&page));
+///
+/// let cached = map.get(&1).unwrap();
+/// assert!(cached.compressed_len() < page.len() / 10);
+/// assert_eq!(page, cached.decompress().unwrap());
+/// ```
+pub struct Compressed<T> {
+    bytes: Box<[u8]>,
+    _t: PhantomData<fn() -> T>,
+}
+
+impl<T: Persist> Compressed<T> {
+    pub fn new(value: &T) -> Self {
+        let mut bytes = Vec::new();
+
+        // writing to a Vec cannot fail.
+        let _ = value.write_to(&mut bytes);
+
+        Compressed {
+            bytes: compress(&bytes).into_boxed_slice(),
+            _t: PhantomData,
+        }
+    }
+
+    /// Returns the number of bytes taken by the compressed value.
+    pub fn compressed_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn decompress(&self) -> io::Result<T> {
+        T::read_from(&mut &decompress(&self.bytes)?[..])
+    }
+}
+
+impl<T> Clone for Compressed<T> {
+    fn clone(&self) -> Self {
+        Compressed {
+            bytes: self.bytes.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T> Debug for Compressed<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Compressed")
+            .field("compressed_len", &self.bytes.len())
+            .finish()
+    }
+}
+
+/// Encodes `input` as its length followed by a list of literal runs, each
+/// followed by a back reference into the already decoded bytes, a zero length
+/// reference ending the list.
+fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 2);
+    write_varint(&mut out, input.len());
+    let mut table = vec![usize::MAX; 1 << TABLE_BITS];
+    let mut anchor = 0;
+    let mut i = 0;
+
+    while i + MIN_MATCH <= input.len() {
+        let word = u32::from_le_bytes(input[i..i + MIN_MATCH].try_into().unwrap());
+        let slot = (word.wrapping_mul(2654435761) >> (32 - TABLE_BITS)) as usize;
+        let candidate = std::mem::replace(&mut table[slot], i);
+
+        if candidate == usize::MAX
+            || input[candidate..candidate + MIN_MATCH] != input[i..i + MIN_MATCH]
+        {
+            i += 1;
+            continue;
+        }
+
+        let mut len = MIN_MATCH;
+
+        while i + len < input.len() && input[candidate + len] == input[i + len] {
+            len += 1;
+        }
+
+        write_varint(&mut out, i - anchor);
+        out.extend_from_slice(&input[anchor..i]);
+        write_varint(&mut out, len);
+        write_varint(&mut out, i - candidate);
+
+        i += len;
+        anchor = i;
+    }
+
+    write_varint(&mut out, input.len() - anchor);
+    out.extend_from_slice(&input[anchor..]);
+    write_varint(&mut out, 0);
+    out
+}
+
+fn decompress(mut input: &[u8]) -> io::Result<Vec<u8>> {
+    let total = read_varint(&mut input)?;
+    let mut out = Vec::new();
+
+    // a corrupted length fails here instead of aborting on allocation.
+    out.try_reserve_exact(total).map_err(|_| corrupted())?;
+
+    // the references are bounded by the length, so a corrupted value cannot
+    // make the output grow without limit.
+    let fits = |out: &Vec<u8>, len: usize| len <= total - out.len();
+
+    loop {
+        let literals = read_varint(&mut input)?;
+        let (run, rest) = input
+            .split_at_checked(literals)
+            .filter(|_| fits(&out, literals))
+            .ok_or_else(corrupted)?;
+
+        out.extend_from_slice(run);
+        input = rest;
+
+        let len = read_varint(&mut input)?;
+
+        if len == 0 {
+            return match out.len() == total {
+                true => Ok(out),
+                false => Err(corrupted()),
+            };
+        }
+
+        let offset = read_varint(&mut input)?;
+
+        if offset == 0 || offset > out.len() || !fits(&out, len) {
+            return Err(corrupted());
+        }
+
+        // the reference may overlap the bytes it produces.
+        let start = out.len() - offset;
+
+        for i in start..start + len {
+            out.push(out[i]);
+        }
+    }
+}
+
+fn corrupted() -> Error {
+    Error::new(ErrorKind::InvalidData, "corrupted compressed value")
+}
+
+fn read_varint(input: &mut &[u8]) -> io::Result<usize> {
+    let mut value = 0usize;
+
+    for shift in (0..usize::BITS).step_by(7) {
+        let (&byte, rest) = input.split_first().ok_or_else(corrupted)?;
+        *input = rest;
+        value |= usize::from(byte & 0x7f) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(corrupted())
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    out.push(value as u8);
+}
+
+#[test]
+fn test_compress() {
+    for input in [
+        &b""[..],
+        b"abc",
+        b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+        b"the quick brown fox, the quick brown fox, the lazy dog",
+    ] {
+        assert_eq!(input, decompress(&compress(input)).unwrap());
+    }
+
+    let text = "cache line ".repeat(1000);
+    let compressed = Compressed::new(&text);
+    assert!(compressed.compressed_len() < 100);
+    assert_eq!(text, compressed.decompress().unwrap());
+
+    assert!(decompress(&[0, 0, 4, 1]).is_err());
+
+    // a huge reference is rejected instead of exhausting the memory.
+    let mut hostile = vec![8, 1, b'a'];
+    write_varint(&mut hostile, usize::MAX >> 8);
+    hostile.push(1);
+    assert!(decompress(&hostile).is_err());
+}
@@ -0,0 +1,159 @@
+//! A fixed size map of counters, for rate limiting and metrics per key.
+use crate::{FixedSizeLruMap, MapGuard};
+use parking_lot::Mutex;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Counters for the most recently incremented keys, each restarting from zero
+/// once its window has elapsed.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::FixedSizeLruCounter;
+/// use std::time::Duration;
+///
+/// // at most 2 requests per client per second.
+/// let requests = FixedSizeLruCounter::with_ttl(10_000, Duration::from_secs(1));
+///
+/// assert!(requests.incr("10.0.0.1", 1) <= 2);
+/// assert!(requests.incr("10.0.0.1", 1) <= 2);
+/// assert!(requests.incr("10.0.0.1", 1) > 2);
+/// ```
+pub struct FixedSizeLruCounter<K, S = RandomState> {
+    map: FixedSizeLruMap<K, Counter, S>,
+    ttl: Option<Duration>,
+}
+
+struct Counter {
+    /// The count and the instant its window started.
+    window: Mutex<(u64, Instant)>,
+}
+
+impl<K> FixedSizeLruCounter<K>
+where
+    K: Clone + Eq + Hash,
+{
+    /// Creates counters that never restart.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_map(FixedSizeLruMap::with_capacity(capacity), None)
+    }
+
+    /// Creates counters restarting from zero `ttl` after their first increment.
+    pub fn with_ttl(capacity: usize, ttl: Duration) -> Self {
+        Self::with_map(FixedSizeLruMap::with_capacity(capacity), Some(ttl))
+    }
+}
+
+impl<K, S> FixedSizeLruCounter<K, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    fn with_map(map: FixedSizeLruMap<K, Counter, S>, ttl: Option<Duration>) -> Self {
+        FixedSizeLruCounter { map, ttl }
+    }
+
+    /// Returns the count of `key`, 0 if it was evicted or its window elapsed.
+    pub fn get(&self, key: &K) -> u64 {
+        match self.map.get(key) {
+            Some(counter) => self.current(&mut counter.window.lock()),
+            None => 0,
+        }
+    }
+
+    /// Adds `n` to the count of `key`, returning the new count.
+    pub fn incr(&self, key: K, n: u64) -> u64 {
+        let counter = match self.map.get(&key) {
+            Some(counter) => counter,
+            None => self.insert_absent(key),
+        };
+
+        let mut count = counter.window.lock();
+        let total = self.current(&mut count).saturating_add(n);
+        count.0 = total;
+        total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the number of keys, counting those whose window elapsed.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Forgets the count of `key`, returning it.
+    pub fn reset(&self, key: &K) -> u64 {
+        match self.map.remove(key) {
+            Some(counter) => self.current(&mut counter.window.lock()),
+            None => 0,
+        }
+    }
+
+    /// Creates the counter of `key` unless another thread did first, returning
+    /// the counter that won.
+    fn insert_absent(&self, key: K) -> MapGuard<Counter> {
+        let mut map = self.map.map.write();
+
+        if let Some(counter) = map.get(&key) {
+            self.map.update_guard_age(counter);
+            return counter.clone();
+        }
+
+        let mut notices = Vec::new();
+        let counter = Arc::new(Counter {
+            window: Mutex::new((0, Instant::now())),
+        });
+        let (counter, _) = self.map.insert_locked(&mut map, key, counter, &mut notices);
+
+        drop(map);
+        self.map.notify(notices);
+        counter
+    }
+
+    /// Returns the count, restarting its window first if it has elapsed.
+    fn current(&self, (count, start): &mut (u64, Instant)) -> u64 {
+        if self.ttl.is_some_and(|ttl| start.elapsed() >= ttl) {
+            *count = 0;
+            *start = Instant::now();
+        }
+
+        *count
+    }
+}
+
+#[test]
+fn test_lru_counter() {
+    let counter = FixedSizeLruCounter::with_ttl(2, Duration::from_millis(50));
+    assert_eq!(2, counter.incr("a", 2));
+    assert_eq!(5, counter.incr("a", 3));
+    assert_eq!(1, counter.incr("b", 1));
+
+    // "a" is the least recently used.
+    counter.incr("c", 1);
+    assert_eq!(0, counter.get(&"a"));
+
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(0, counter.get(&"b"));
+    assert_eq!(4, counter.incr("c", 4));
+    assert_eq!(4, counter.reset(&"c"));
+    assert_eq!(1, counter.len());
+}
+
+#[test]
+fn test_concurrent_first_incr() {
+    let counter = FixedSizeLruCounter::with_capacity(10);
+
+    // a thread creating the counter after another did increments the winner.
+    let first = counter.insert_absent("a");
+    first.window.lock().0 = 3;
+    let second = counter.insert_absent("a");
+
+    assert!(MapGuard::ptr_eq(&first, &second));
+    assert_eq!(4, counter.incr("a", 1));
+}
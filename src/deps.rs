@@ -0,0 +1,139 @@
+//! Cascading removal of the entries derived from other entries.
+use crate::FixedSizeLruMap;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash},
+    sync::atomic::{AtomicBool, Ordering::Relaxed},
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Declares that the value of `child` is derived from the one of `parent`,
+    /// so removing `parent` also removes `child`, and in turn its own dependents.
+    ///
+    /// Only explicit removals cascade: an evicted parent leaves its dependents
+    /// cached, they are still valid. The dependency is forgotten once the parent
+    /// has been removed.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// map.insert("rates", 1.1);
+    /// map.insert("price_eur", 100.0);
+    /// map.insert("price_usd", 110.0);
+    /// map.depends_on("price_usd", "rates");
+    ///
+    /// map.remove(&"rates");
+    /// assert!(map.get(&"price_usd").is_none());
+    /// assert!(map.get(&"price_eur").is_some());
+    /// ```
+    pub fn depends_on(&self, child: K, parent: K) {
+        let mut edges = self.dependencies.edges.lock();
+
+        // the edges of the keys gone without being removed pile up, drop
+        // them once they outnumber the entries.
+        if edges.len() > 2 * self.capacity.max(1) {
+            self.prune_edges(&mut edges);
+        }
+
+        edges.entry(parent).or_default().push(child);
+        self.dependencies.any.store(true, Relaxed);
+    }
+
+    /// Drops the dependencies of the keys no longer cached.
+    pub(crate) fn prune_dependencies(&self) {
+        if self.dependencies.any.load(Relaxed) {
+            self.prune_edges(&mut self.dependencies.edges.lock());
+        }
+    }
+
+    fn prune_edges(&self, edges: &mut HashMap<K, Vec<K>>) {
+        let map = self.map.read();
+
+        edges.retain(|parent, children| {
+            children.retain(|child| map.contains_key(child));
+            !children.is_empty() && map.contains_key(parent)
+        });
+    }
+}
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Removes the entries depending on `parent`, which has just been removed.
+    pub(crate) fn remove_dependents(&self, parent: &K) {
+        if !self.dependencies.any.load(Relaxed) {
+            return;
+        }
+
+        let Some(children) = self.dependencies.edges.lock().remove(parent) else {
+            return;
+        };
+
+        for child in &children {
+            self.remove(child);
+        }
+    }
+}
+
+/// The keys depending on each key.
+pub(crate) struct Dependencies<K> {
+    /// Whether a dependency was ever declared, to keep removals lock free otherwise.
+    any: AtomicBool,
+    edges: Mutex<HashMap<K, Vec<K>>>,
+}
+
+impl<K> Dependencies<K> {
+    pub fn clear(&self) {
+        if self.any.load(Relaxed) {
+            self.edges.lock().clear();
+        }
+    }
+}
+
+impl<K> Default for Dependencies<K> {
+    fn default() -> Self {
+        Dependencies {
+            any: AtomicBool::new(false),
+            edges: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[test]
+fn test_cascading_removal() {
+    let map = FixedSizeLruMap::with_capacity(10);
+
+    for key in 1..=4 {
+        map.insert(key, ());
+    }
+
+    // 3 depends on 2 which depends on 1, and 1 depends on 3.
+    map.depends_on(2, 1);
+    map.depends_on(3, 2);
+    map.depends_on(1, 3);
+
+    map.remove(&1);
+    assert_eq!(vec![4], map.view().keys().copied().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_prune_dependencies() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    map.insert(1, ());
+    map.insert(2, ());
+    map.depends_on(2, 1);
+
+    // 1 is evicted, leaving its dependency behind until the maintenance.
+    map.insert(3, ());
+    map.run_maintenance();
+    assert!(map.dependencies.edges.lock().is_empty());
+}
@@ -0,0 +1,141 @@
+//! Lookups by a borrowed view of the keys, without building an owned key.
+use std::{
+    borrow::Borrow,
+    hash::{Hash, Hasher},
+};
+
+/// A query standing for a key of type `K` in the lookups of a
+/// [`FixedSizeLruMap`](crate::FixedSizeLruMap).
+///
+/// Every `Q` the keys borrow as is a query, e.g. a `&str` for `String` keys.
+/// A [`Pair`] looks up the tuple keys whose elements borrow as its own.
+pub trait Equivalent<K> {
+    /// The form both the query and the keys are hashed and compared as.
+    type Key: ?Sized + Eq + Hash;
+
+    fn as_key(&self) -> &Self::Key;
+}
+
+impl<Q, K> Equivalent<K> for Q
+where
+    K: Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+{
+    type Key = Q;
+
+    fn as_key(&self) -> &Q {
+        self
+    }
+}
+
+/// A view of a tuple key made of references, so a `(String, u32)` key can be
+/// looked up from a `&str` and a `&u32` without allocating.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::{FixedSizeLruMap, Pair};
+///
+/// let map = FixedSizeLruMap::with_capacity(10);
+/// map.insert(("invoice".to_owned(), 42), 100);
+///
+/// assert_eq!(100, *map.get(&Pair("invoice", &42)).unwrap());
+/// assert!(map.remove(&Pair("invoice", &42)).is_some());
+/// ```
+#[derive(Debug)]
+pub struct Pair<'a, A: ?Sized, B: ?Sized>(pub &'a A, pub &'a B);
+
+// not derived, which would require the referenced types to be `Clone`.
+impl<A: ?Sized, B: ?Sized> Clone for Pair<'_, A, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A: ?Sized, B: ?Sized> Copy for Pair<'_, A, B> {}
+
+/// The form a [`Pair`] and the tuple keys are compared as.
+pub trait PairKey<A: ?Sized, B: ?Sized> {
+    fn parts(&self) -> (&A, &B);
+}
+
+impl<A: ?Sized, B: ?Sized> PairKey<A, B> for Pair<'_, A, B> {
+    fn parts(&self) -> (&A, &B) {
+        (self.0, self.1)
+    }
+}
+
+impl<A, B, A2, B2> PairKey<A, B> for (A2, B2)
+where
+    A: ?Sized,
+    A2: Borrow<A>,
+    B: ?Sized,
+    B2: Borrow<B>,
+{
+    fn parts(&self) -> (&A, &B) {
+        (self.0.borrow(), self.1.borrow())
+    }
+}
+
+impl<'a, A, B, A2, B2> Borrow<dyn PairKey<A, B> + 'a> for (A2, B2)
+where
+    A: ?Sized,
+    A2: Borrow<A> + 'a,
+    B: ?Sized,
+    B2: Borrow<B> + 'a,
+{
+    fn borrow(&self) -> &(dyn PairKey<A, B> + 'a) {
+        self
+    }
+}
+
+impl<'a, A, B, A2, B2> Equivalent<(A2, B2)> for Pair<'a, A, B>
+where
+    A: Eq + Hash + ?Sized,
+    A2: Borrow<A>,
+    B: Eq + Hash + ?Sized,
+    B2: Borrow<B>,
+{
+    type Key = dyn PairKey<A, B> + 'a;
+
+    fn as_key(&self) -> &Self::Key {
+        self
+    }
+}
+
+// hashed like a tuple, element after element, so a view finds its key.
+impl<A: Hash + ?Sized, B: Hash + ?Sized> Hash for dyn PairKey<A, B> + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let (a, b) = self.parts();
+        a.hash(state);
+        b.hash(state);
+    }
+}
+
+impl<A: Eq + ?Sized, B: Eq + ?Sized> PartialEq for dyn PairKey<A, B> + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts() == other.parts()
+    }
+}
+
+impl<A: Eq + ?Sized, B: Eq + ?Sized> Eq for dyn PairKey<A, B> + '_ {}
+
+#[test]
+fn test_pair_lookup() {
+    use crate::FixedSizeLruMap;
+
+    let map = FixedSizeLruMap::with_capacity(10);
+    map.insert(("a".to_owned(), 1u32), 'a');
+    map.insert(("a".to_owned(), 2), 'b');
+
+    let key: (&str, u32) = ("a", 2);
+    assert_eq!('b', *map.get(&Pair(key.0, &key.1)).unwrap());
+    assert!(map.contains_key(&Pair("a", &1)));
+    assert!(!map.contains_key(&Pair("b", &1)));
+
+    // the owned keys still work alongside the views.
+    assert_eq!('a', *map.get(&("a".to_owned(), 1)).unwrap());
+
+    assert!(map.remove_if(&Pair("a", &1), |v| **v == 'x').is_none());
+    assert_eq!('a', *map.remove(&Pair("a", &1)).unwrap());
+    assert_eq!(1, map.len());
+}
@@ -0,0 +1,239 @@
+//! Unbounded channels publishing what happens to the entries of a map.
+use crate::MapGuard;
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        Arc, Weak,
+    },
+    task::{Context, Poll, Waker},
+};
+
+/// A mutation of a [`FixedSizeLruMap`](crate::FixedSizeLruMap).
+pub enum CacheEvent<K, V> {
+    /// A value was inserted for a new key.
+    Insert(K, MapGuard<V>),
+    /// The value of a key was replaced.
+    Update {
+        key: K,
+        old: MapGuard<V>,
+        new: MapGuard<V>,
+    },
+    /// An entry was explicitly removed.
+    Remove(K, MapGuard<V>),
+    /// An entry was evicted to make room for a new one.
+    Evict(K, MapGuard<V>),
+}
+
+impl<K, V> CacheEvent<K, V> {
+    /// Returns the key of the mutated entry.
+    pub fn key(&self) -> &K {
+        match self {
+            CacheEvent::Insert(k, _)
+            | CacheEvent::Update { key: k, .. }
+            | CacheEvent::Remove(k, _)
+            | CacheEvent::Evict(k, _) => k,
+        }
+    }
+}
+
+/// Why an entry left a map.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum RemovalCause {
+    /// Evicted to keep the map within its capacity.
+    Capacity,
+    /// Removed by the user.
+    Explicit,
+    /// Replaced by the insertion of a new value for its key.
+    Replaced,
+}
+
+/// Callback told of every value inserted for a new key, see
+/// [`on_insert`](crate::FixedSizeLruMapBuilder::on_insert).
+pub(crate) type InsertHook<K, V> = Box<dyn Fn(&K, &MapGuard<V>) + Send + Sync>;
+
+/// Callback told of every replaced value, see
+/// [`on_update`](crate::FixedSizeLruMapBuilder::on_update).
+pub(crate) type UpdateHook<K, V> = Box<dyn Fn(&K, &MapGuard<V>, &MapGuard<V>) + Send + Sync>;
+
+/// Callback told of every entry leaving the map, see
+/// [`removal_listener`](crate::FixedSizeLruMapBuilder::removal_listener).
+pub(crate) type RemovalListener<K, V> = Box<dyn Fn(K, MapGuard<V>, RemovalCause) + Send + Sync>;
+
+/// A change made under the lock of the map, told to the listeners once the lock is released.
+pub(crate) enum Notice<K, V> {
+    Inserted(K, MapGuard<V>),
+    Removed(K, MapGuard<V>, RemovalCause),
+    Updated(K, MapGuard<V>, MapGuard<V>),
+}
+
+impl<K: Clone, V> Clone for CacheEvent<K, V> {
+    fn clone(&self) -> Self {
+        match self {
+            CacheEvent::Insert(k, v) => CacheEvent::Insert(k.clone(), v.clone()),
+            CacheEvent::Update { key, old, new } => CacheEvent::Update {
+                key: key.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            },
+            CacheEvent::Remove(k, v) => CacheEvent::Remove(k.clone(), v.clone()),
+            CacheEvent::Evict(k, v) => CacheEvent::Evict(k.clone(), v.clone()),
+        }
+    }
+}
+
+/// Receives the events published by a map.
+///
+/// This is an async stream: [`poll_recv`](Self::poll_recv) can back a
+/// `Stream` implementation.
+///
+/// Publishing never blocks the map: the events are queued until received.
+/// Dropping the receiver unsubscribes it.
+pub struct EventReceiver<T>(Arc<Mutex<Queue<T>>>);
+
+struct Queue<T> {
+    capacity: usize,
+    closed: bool,
+    dropped: u64,
+    items: VecDeque<T>,
+    waker: Option<Waker>,
+}
+
+impl<T> EventReceiver<T> {
+    /// Blocks the thread until the next event, returning `None` once the map has been dropped.
+    pub fn blocking_recv(&mut self) -> Option<T> {
+        crate::future::block_on(self.recv())
+    }
+
+    /// Returns how many events were discarded because the receiver was full,
+    /// see [`subscribe`](crate::FixedSizeLruMap::subscribe).
+    pub fn dropped(&self) -> u64 {
+        self.0.lock().dropped
+    }
+
+    /// Waits for the next event, returning `None` once the map has been dropped.
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Polls for the next event, returning `Ready(None)` once the map has been dropped.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut queue = self.0.lock();
+
+        match queue.items.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if queue.closed => Poll::Ready(None),
+            None => {
+                queue.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Returns the next event if one is already queued.
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.0.lock().items.pop_front()
+    }
+}
+
+/// The sending side, owned by the map.
+pub(crate) struct Subscribers<T> {
+    /// Whether `queues` is not empty, so the writes of a map nobody listens to
+    /// take no lock. The dropped receivers are only noticed by the next send.
+    any: AtomicBool,
+    queues: Mutex<Vec<Weak<Mutex<Queue<T>>>>>,
+}
+
+impl<T> Default for Subscribers<T> {
+    fn default() -> Self {
+        Subscribers {
+            any: AtomicBool::new(false),
+            queues: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<T> Subscribers<T> {
+    pub fn subscribe(&self) -> EventReceiver<T> {
+        self.subscribe_bounded(usize::MAX)
+    }
+
+    /// Subscribes a receiver keeping only the last `capacity` events.
+    pub fn subscribe_bounded(&self, capacity: usize) -> EventReceiver<T> {
+        let queue = Arc::new(Mutex::new(Queue {
+            capacity,
+            closed: false,
+            dropped: 0,
+            items: VecDeque::new(),
+            waker: None,
+        }));
+
+        self.queues.lock().push(Arc::downgrade(&queue));
+        self.any.store(true, Relaxed);
+        EventReceiver(queue)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.any.load(Relaxed)
+    }
+
+    /// Sends the event built by `f` to every receiver; `f` is not called without any.
+    pub fn send<F>(&self, f: F)
+    where
+        F: FnOnce() -> T,
+        T: Clone,
+    {
+        if self.is_empty() {
+            return;
+        }
+
+        let mut subscribers = self.queues.lock();
+
+        if subscribers.is_empty() {
+            return;
+        }
+
+        let item = f();
+
+        subscribers.retain(|queue| {
+            let Some(queue) = queue.upgrade() else {
+                return false;
+            };
+
+            let mut queue = queue.lock();
+
+            if queue.items.len() >= queue.capacity {
+                queue.items.pop_front();
+                queue.dropped += 1;
+            }
+
+            queue.items.push_back(item.clone());
+
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+
+            true
+        });
+
+        self.any.store(!subscribers.is_empty(), Relaxed);
+    }
+}
+
+impl<T> Drop for Subscribers<T> {
+    fn drop(&mut self) {
+        for queue in self.queues.get_mut().drain(..) {
+            if let Some(queue) = queue.upgrade() {
+                let mut queue = queue.lock();
+                queue.closed = true;
+
+                if let Some(waker) = queue.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
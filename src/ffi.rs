@@ -0,0 +1,162 @@
+//! A C interface over byte string keys and values.
+//!
+//! ```c
+//! LruMap *map = lru_map_create(100);
+//! lru_map_insert(map, (const uint8_t *)"a", 1, (const uint8_t *)"1", 1);
+//!
+//! LruValue *value = lru_map_get(map, (const uint8_t *)"a", 1);
+//! if (value) {
+//!     size_t len;
+//!     const uint8_t *data = lru_value_data(value, &len);
+//!     /* ... */
+//!     lru_value_free(value);
+//! }
+//!
+//! lru_map_free(map);
+//! ```
+//!
+//! The handles are opaque: a map is safe to use from several threads, a value
+//! stays readable after its entry is evicted, until it is freed.
+//!
+//! The functions are declared in `include/fixed_size_lru_map.h`, the crate
+//! builds a static and a shared library to link them from.
+use crate::{FixedSizeLruMap, MapGuard};
+use std::{mem::ManuallyDrop, ptr::null_mut, slice};
+
+/// A map handle, see [`lru_map_create`].
+pub struct LruMap(FixedSizeLruMap<Box<[u8]>, Box<[u8]>>);
+
+/// A value handle, see [`lru_map_get`].
+///
+/// It is the guard of the value itself, so a lookup allocates nothing.
+pub struct LruValue {
+    _opaque: [u8; 0],
+}
+
+type Guard = MapGuard<Box<[u8]>>;
+
+/// Reads `len` bytes at `ptr`, which may be null when `len` is zero.
+unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// Creates a map holding up to `capacity` entries, to be freed with [`lru_map_free`].
+#[no_mangle]
+pub extern "C" fn lru_map_create(capacity: usize) -> *mut LruMap {
+    Box::into_raw(Box::new(LruMap(FixedSizeLruMap::with_capacity(capacity))))
+}
+
+/// Frees a map, the values still held stay valid.
+///
+/// # Safety
+/// `map` must come from [`lru_map_create`] and not be used afterwards. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn lru_map_free(map: *mut LruMap) {
+    if !map.is_null() {
+        drop(Box::from_raw(map));
+    }
+}
+
+/// Returns the value of a key, or null when missing, to be freed with [`lru_value_free`].
+///
+/// # Safety
+/// `map` must be a live map and `key` must point to `key_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn lru_map_get(
+    map: *const LruMap,
+    key: *const u8,
+    key_len: usize,
+) -> *mut LruValue {
+    match (*map).0.get(bytes(key, key_len)) {
+        Some(value) => MapGuard::into_raw(value).cast_mut().cast(),
+        None => null_mut(),
+    }
+}
+
+/// Copies and inserts a value, replacing the previous one of the key.
+///
+/// # Safety
+/// `map` must be a live map, `key` and `value` must point to `key_len` and
+/// `value_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn lru_map_insert(
+    map: *const LruMap,
+    key: *const u8,
+    key_len: usize,
+    value: *const u8,
+    value_len: usize,
+) {
+    (*map)
+        .0
+        .insert(bytes(key, key_len).into(), bytes(value, value_len).into());
+}
+
+/// Returns the number of entries.
+///
+/// # Safety
+/// `map` must be a live map.
+#[no_mangle]
+pub unsafe extern "C" fn lru_map_len(map: *const LruMap) -> usize {
+    (*map).0.len()
+}
+
+/// Removes a key, returning whether it was present.
+///
+/// # Safety
+/// `map` must be a live map and `key` must point to `key_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn lru_map_remove(
+    map: *const LruMap,
+    key: *const u8,
+    key_len: usize,
+) -> bool {
+    (*map).0.remove(bytes(key, key_len)).is_some()
+}
+
+/// Returns the bytes of a value and writes their length to `len`.
+///
+/// # Safety
+/// `value` must be a live value and `len` must be writable. The bytes are
+/// valid until the value is freed.
+#[no_mangle]
+pub unsafe extern "C" fn lru_value_data(value: *const LruValue, len: *mut usize) -> *const u8 {
+    // borrowed from the handle, which keeps its reference.
+    let data = ManuallyDrop::new(Guard::from_raw(value.cast()));
+    *len = data.len();
+    data.as_ptr()
+}
+
+/// Frees a value returned by [`lru_map_get`].
+///
+/// # Safety
+/// `value` must not be used afterwards. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn lru_value_free(value: *mut LruValue) {
+    if !value.is_null() {
+        drop(Guard::from_raw(value.cast()));
+    }
+}
+
+#[test]
+fn test_ffi() {
+    unsafe {
+        let map = lru_map_create(1);
+        lru_map_insert(map, b"a".as_ptr(), 1, b"one".as_ptr(), 3);
+
+        let value = lru_map_get(map, b"a".as_ptr(), 1);
+        lru_map_insert(map, std::ptr::null(), 0, b"empty".as_ptr(), 5);
+        assert!(lru_map_get(map, b"a".as_ptr(), 1).is_null());
+        assert_eq!(1, lru_map_len(map));
+
+        let mut len = 0;
+        let data = lru_value_data(value, &mut len);
+        assert_eq!(b"one", slice::from_raw_parts(data, len));
+
+        lru_map_free(map);
+        lru_value_free(value);
+    }
+}
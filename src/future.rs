@@ -0,0 +1,387 @@
+//! Asynchronous initialization of the values.
+use crate::{FixedSizeLruMap, MapGuard};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    future::{poll_fn, Future},
+    hash::{BuildHasher, Hash},
+    sync::Arc,
+    task::{Poll, Waker},
+    time::Instant,
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Gets the value of `key` or initializes it with the future returned by `f`.
+    ///
+    /// The map is never locked while the future is awaited, so this can be
+    /// called from async handlers loading the values from the network.
+    /// Concurrent misses on the same key await the same load.
+    pub async fn get_or_init_async<F, Fut>(&self, key: K, f: F) -> MapGuard<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        match self
+            .get_or_try_init_async(key, || async { Ok::<_, Infallible>(f().await) })
+            .await
+        {
+            Ok(value) => value,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Gets the value of `key` or tries to initialize it with the future returned by `f`.
+    ///
+    /// Nothing is cached when the future fails, the next call will try again.
+    /// The callers that were waiting on a failed load start their own.
+    pub async fn get_or_try_init_async<F, Fut, E>(&self, key: K, f: F) -> Result<MapGuard<V>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+    {
+        let mut f = Some(f);
+
+        loop {
+            if let Some(value) = self.get(&key) {
+                return Ok(value);
+            }
+
+            let (load, leader) = {
+                let mut loads = self.loads.0.lock();
+
+                match loads.get(&key) {
+                    Some(load) => (Arc::clone(load), false),
+                    None => {
+                        // the previous load may have completed since the miss.
+                        if let Some(value) = self.get(&key) {
+                            return Ok(value);
+                        }
+
+                        let load = Arc::new(Load::default());
+                        loads.insert(key.clone(), Arc::clone(&load));
+                        (load, true)
+                    }
+                }
+            };
+
+            if leader {
+                let mut leader = Leader {
+                    key: &key,
+                    load: &load,
+                    loads: &self.loads,
+                    value: None,
+                };
+
+                let f = f.take().expect("a single load per call");
+
+                let _permit = match &self.loaders {
+                    Some(loaders) => Some(loaders.acquire_async().await),
+                    None => None,
+                };
+
+                // the clock is only read to time the load for an instrument.
+                let value = match &self.instrument {
+                    Some(instrument) => {
+                        instrument.load_started(&key);
+                        let start = Instant::now();
+                        let value = f().await;
+                        instrument.load_finished(&key, start.elapsed());
+                        value
+                    }
+                    None => f().await,
+                };
+
+                let value = value?;
+                let value = self.insert(key.clone(), value).0;
+
+                leader.value = Some(value.clone());
+                return Ok(value);
+            }
+
+            if let Some(value) = load.wait().await {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Loads the missing `keys`, running up to `parallelism` loads concurrently.
+    ///
+    /// Returns the outcome of every key, in completion order. The keys already
+    /// present are reported as loaded without calling `loader`.
+    pub async fn warm<I, F, Fut, E>(
+        &self,
+        keys: I,
+        parallelism: usize,
+        loader: F,
+    ) -> Vec<(K, Result<MapGuard<V>, E>)>
+    where
+        I: IntoIterator<Item = K>,
+        F: Fn(&K) -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+    {
+        let loader = &loader;
+        let mut keys = keys.into_iter();
+        let mut out = Vec::new();
+        let mut running = Vec::new();
+
+        poll_fn(|cx| loop {
+            while running.len() < parallelism.max(1) {
+                let Some(key) = keys.next() else {
+                    break;
+                };
+
+                running.push(Box::pin(async move {
+                    let r = self
+                        .get_or_try_init_async(key.clone(), || loader(&key))
+                        .await;
+                    (key, r)
+                }));
+            }
+
+            if running.is_empty() {
+                return Poll::Ready(());
+            }
+
+            let len = running.len();
+            let mut i = 0;
+
+            while i < running.len() {
+                match running[i].as_mut().poll(cx) {
+                    Poll::Ready(r) => {
+                        out.push(r);
+                        drop(running.swap_remove(i));
+                    }
+                    Poll::Pending => i += 1,
+                }
+            }
+
+            // poll again only when a slot was freed for another key.
+            if running.len() == len {
+                return Poll::Pending;
+            }
+        })
+        .await;
+
+        out
+    }
+}
+
+/// The loads currently in flight, by key.
+pub(crate) struct Loads<K, V>(Mutex<HashMap<K, Arc<Load<V>>>>);
+
+impl<K, V> Default for Loads<K, V> {
+    fn default() -> Self {
+        Loads(Mutex::new(HashMap::new()))
+    }
+}
+
+impl<K, V> Loads<K, V>
+where
+    K: Eq + Hash,
+{
+    /// Returns the load of `key` in flight, if any.
+    pub fn get(&self, key: &K) -> Option<Arc<Load<V>>> {
+        self.0.lock().get(key).cloned()
+    }
+}
+
+pub(crate) struct Load<V>(Mutex<LoadState<V>>);
+
+enum LoadState<V> {
+    Pending(Vec<Waker>),
+    Done(Option<MapGuard<V>>),
+}
+
+impl<V> Default for Load<V> {
+    fn default() -> Self {
+        Load(Mutex::new(LoadState::Pending(Vec::new())))
+    }
+}
+
+impl<V> Load<V> {
+    /// Resolves to the loaded value, or `None` when the load failed or was cancelled.
+    pub async fn wait(&self) -> Option<MapGuard<V>> {
+        poll_fn(|cx| match &mut *self.0.lock() {
+            LoadState::Done(value) => Poll::Ready(value.clone()),
+            LoadState::Pending(wakers) => {
+                if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// Publishes the outcome of a load when dropped, even if the load future is cancelled.
+struct Leader<'a, K, V>
+where
+    K: Eq + Hash,
+{
+    key: &'a K,
+    load: &'a Arc<Load<V>>,
+    loads: &'a Loads<K, V>,
+    value: Option<MapGuard<V>>,
+}
+
+impl<K, V> Drop for Leader<'_, K, V>
+where
+    K: Eq + Hash,
+{
+    fn drop(&mut self) {
+        {
+            let mut loads = self.loads.0.lock();
+
+            if loads
+                .get(self.key)
+                .is_some_and(|l| Arc::ptr_eq(l, self.load))
+            {
+                loads.remove(self.key);
+            }
+        }
+
+        let state = std::mem::replace(&mut *self.load.0.lock(), LoadState::Done(self.value.take()));
+
+        if let LoadState::Pending(wakers) = state {
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
+}
+
+/// Drives a future to completion on the current thread.
+pub(crate) fn block_on<F: Future>(f: F) -> F::Output {
+    match block_on_until(f, None) {
+        Some(out) => out,
+        None => unreachable!("no deadline"),
+    }
+}
+
+/// Drives a future on the current thread until it completes or `deadline`
+/// passes, returning `None` in the latter case.
+pub(crate) fn block_on_until<F: Future>(f: F, deadline: Option<Instant>) -> Option<F::Output> {
+    use std::{
+        pin::pin,
+        task::{Context, Wake},
+        thread::{self, Thread},
+    };
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(Unpark(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut f = pin!(f);
+
+    loop {
+        match f.as_mut().poll(&mut cx) {
+            Poll::Ready(out) => return Some(out),
+            Poll::Pending => match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+
+                    if now >= deadline {
+                        return None;
+                    }
+
+                    thread::park_timeout(deadline - now);
+                }
+                None => thread::park(),
+            },
+        }
+    }
+}
+
+#[test]
+fn test_get_or_init_async() {
+    let map = FixedSizeLruMap::with_capacity(2);
+
+    block_on(async {
+        assert_eq!(1, *map.get_or_init_async("a", || async { 1 }).await);
+        assert_eq!(1, *map.get_or_init_async("a", || async { 2 }).await);
+    });
+
+    assert_eq!(1, map.len());
+}
+
+#[test]
+fn test_get_or_try_init_async() {
+    let map = FixedSizeLruMap::with_capacity(2);
+
+    block_on(async {
+        let r = map
+            .get_or_try_init_async("a", || async { Err("db down") })
+            .await;
+        assert_eq!(Some("db down"), r.err());
+        assert!(!map.contains_key(&"a"));
+
+        let r = map
+            .get_or_try_init_async("a", || async { Ok::<_, ()>(1) })
+            .await;
+        assert_eq!(1, *r.unwrap());
+    });
+}
+
+#[test]
+fn test_warm() {
+    let map = FixedSizeLruMap::with_capacity(20);
+    map.insert(0, 100);
+
+    let mut out = block_on(map.warm(0..10, 3, |k| {
+        let k = *k;
+        async move {
+            if k == 5 {
+                Err("not found")
+            } else {
+                Ok(k)
+            }
+        }
+    }));
+    out.sort_by_key(|(k, _)| *k);
+
+    assert_eq!(10, out.len());
+    assert_eq!(100, **out[0].1.as_ref().unwrap());
+    assert_eq!(
+        Err("not found"),
+        out[5].1.as_ref().map(|_| ()).map_err(|e| *e)
+    );
+    assert_eq!(9, map.len());
+}
+
+#[test]
+fn test_async_coalescing() {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering::Relaxed},
+        thread::{scope, sleep},
+        time::Duration,
+    };
+
+    let map = FixedSizeLruMap::with_capacity(2);
+    let calls = AtomicUsize::new(0);
+
+    scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                let v = block_on(map.get_or_init_async(1, || async {
+                    calls.fetch_add(1, Relaxed);
+                    sleep(Duration::from_millis(50));
+                    'a'
+                }));
+                assert_eq!('a', *v);
+            });
+        }
+    });
+
+    assert_eq!(1, calls.into_inner());
+}
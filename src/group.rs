@@ -0,0 +1,147 @@
+//! Namespaces sharing the capacity of a single map.
+use crate::{FixedSizeLruMap, MapGuard, Pair};
+use parking_lot::Mutex;
+use std::{collections::HashMap, hash::Hash};
+
+/// A map split into named namespaces competing for the same capacity.
+///
+/// Unlike separate maps, a busy namespace can use the room a quiet one does not
+/// need, while each namespace can still be invalidated on its own.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::CacheGroup;
+///
+/// let group = CacheGroup::with_capacity(100);
+/// group.namespace("users").insert(1, "ann".to_owned());
+/// group.namespace("teams").insert(1, "core".to_owned());
+///
+/// assert_eq!(1, group.namespace("users").invalidate());
+/// assert!(group.namespace("users").get(&1).is_none());
+/// assert_eq!("core", *group.namespace("teams").get(&1).unwrap());
+/// ```
+pub struct CacheGroup<K, V> {
+    map: FixedSizeLruMap<(u32, K), V>,
+    names: Mutex<HashMap<String, u32>>,
+}
+
+/// The entries of a [`CacheGroup`] in one namespace.
+pub struct Namespace<'a, K, V> {
+    group: &'a CacheGroup<K, V>,
+    id: u32,
+}
+
+impl<K, V> CacheGroup<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_map(FixedSizeLruMap::with_capacity(capacity))
+    }
+
+    /// Shares `map` between the namespaces, its keys are the ids of the
+    /// namespaces with the keys inside them.
+    pub fn with_map(map: FixedSizeLruMap<(u32, K), V>) -> Self {
+        CacheGroup {
+            map,
+            names: Default::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the number of entries across all the namespaces.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the underlying map.
+    pub fn map(&self) -> &FixedSizeLruMap<(u32, K), V> {
+        &self.map
+    }
+
+    /// Returns the namespace called `name`, created on first use.
+    pub fn namespace(&self, name: &str) -> Namespace<'_, K, V> {
+        let mut names = self.names.lock();
+        let next = names.len() as u32;
+        let id = *names.entry(name.to_owned()).or_insert(next);
+        Namespace { group: self, id }
+    }
+}
+
+impl<K, V> Namespace<'_, K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.group.map.contains_key(&Pair(&self.id, key))
+    }
+
+    pub fn get(&self, key: &K) -> Option<MapGuard<V>> {
+        self.group.map.get(&Pair(&self.id, key))
+    }
+
+    pub fn get_or_init<F>(&self, key: K, f: F) -> MapGuard<V>
+    where
+        F: FnOnce() -> V,
+    {
+        self.group.map.get_or_init((self.id, key), f)
+    }
+
+    pub fn insert(&self, key: K, value: V) -> (MapGuard<V>, Option<MapGuard<V>>) {
+        self.group.map.insert((self.id, key), value)
+    }
+
+    /// Removes every entry of the namespace, returning how many there were.
+    pub fn invalidate(&self) -> usize {
+        self.keys()
+            .into_iter()
+            .filter(|key| self.group.map.remove(key).is_some())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries in the namespace.
+    pub fn len(&self) -> usize {
+        self.keys().len()
+    }
+
+    pub fn remove(&self, key: &K) -> Option<MapGuard<V>> {
+        self.group.map.remove(&Pair(&self.id, key))
+    }
+
+    fn keys(&self) -> Vec<(u32, K)> {
+        self.group
+            .map
+            .view()
+            .keys()
+            .filter(|(id, _)| *id == self.id)
+            .cloned()
+            .collect()
+    }
+}
+
+#[test]
+fn test_cache_group() {
+    let group = CacheGroup::with_capacity(3);
+    let users = group.namespace("users");
+    let teams = group.namespace("teams");
+
+    users.insert(1, "ann");
+    users.insert(2, "bob");
+    teams.insert(1, "core");
+
+    // the namespaces share the capacity, the oldest user makes room.
+    teams.insert(2, "docs");
+    assert!(!users.contains_key(&1));
+    assert_eq!(2, teams.len());
+
+    assert_eq!(2, group.namespace("teams").invalidate());
+    assert_eq!(1, group.len());
+    assert_eq!("bob", *users.get(&2).unwrap());
+}
@@ -0,0 +1,183 @@
+//! Updates of the cached values.
+use crate::{weight::Weights, Entry, FixedSizeLruMap, MapGuard};
+use parking_lot::{MappedRwLockWriteGuard, RwLockWriteGuard};
+use std::{
+    hash::{BuildHasher, Hash},
+    ops::{Deref, DerefMut},
+    sync::{atomic::AtomicU64, atomic::Ordering::Relaxed, Arc},
+};
+
+/// Exclusive access to a cached value, returned by [`get_mut`](FixedSizeLruMap::get_mut).
+///
+/// The map stays locked until the guard is dropped.
+pub struct MapGuardMut<'a, K, V> {
+    key: Option<K>,
+    value: MappedRwLockWriteGuard<'a, V>,
+    weights: &'a Weights<K, V>,
+}
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+    V: Clone,
+{
+    /// Returns exclusive access to the value of `key` to update it in place.
+    ///
+    /// The guards already handed out keep seeing the previous value: when the
+    /// entry is shared, it is cloned first. No event is published.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// let (before, _) = map.insert("counter", 1);
+    ///
+    /// *map.get_mut(&"counter").unwrap() += 1;
+    ///
+    /// assert_eq!((1, 2), (*before, *map.get(&"counter").unwrap()));
+    /// ```
+    pub fn get_mut(&self, key: &K) -> Option<MapGuardMut<'_, K, V>> {
+        let value = RwLockWriteGuard::try_map(self.map.write(), |map| {
+            let guard = map.get_mut(key)?;
+
+            self.update_guard_age(guard);
+            self.weights.sub(key, guard);
+
+            if Arc::get_mut(&mut guard.0).is_none() {
+                *guard = MapGuard(Arc::new(Entry {
+                    accessed: AtomicU64::new(guard.0.accessed.load(Relaxed)),
+                    age: AtomicU64::new(guard.age()),
+                    hits: AtomicU64::new(MapGuard::hits(guard)),
+                    loaded: guard.0.loaded,
+                    #[cfg(feature = "std")]
+                    refreshing: Default::default(),
+                    value: Arc::clone(&guard.0.value),
+                }));
+            }
+
+            // unique now, the value itself is cloned if it was shared through into_arc.
+            let entry = Arc::get_mut(&mut guard.0).expect("unique entry");
+            Some(Arc::make_mut(&mut entry.value))
+        })
+        .ok()?;
+
+        Some(MapGuardMut {
+            key: self.weights.enabled().then(|| key.clone()),
+            value,
+            weights: &self.weights,
+        })
+    }
+}
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Replaces the value of `key` by the one computed by `f` from the current
+    /// one, returning the new value or `None` if the key is missing.
+    ///
+    /// `f` runs without any lock held, so an expensive update does not block
+    /// the map. If the value is replaced meanwhile, `f` runs again on the new one.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// map.insert("tags", vec!["a"]);
+    ///
+    /// let tags = map.update_cow(&"tags", |tags| [&tags[..], &["b"]].concat());
+    /// assert_eq!(vec!["a", "b"], *tags.unwrap());
+    /// ```
+    pub fn update_cow<F>(&self, key: &K, mut f: F) -> Option<MapGuard<V>>
+    where
+        F: FnMut(&V) -> V,
+    {
+        let mut current = self.map.read().get(key)?.clone();
+
+        loop {
+            let new = Arc::new(f(&current));
+            let mut map = self.map.write();
+
+            match map.get(key) {
+                Some(cur) if MapGuard::ptr_eq(cur, &current) => {
+                    let mut notices = Vec::new();
+                    let (guard, _) = self.insert_locked(&mut map, key.clone(), new, &mut notices);
+
+                    drop(map);
+                    self.notify(notices);
+                    return Some(guard);
+                }
+                Some(cur) => current = cur.clone(),
+                None => return None,
+            }
+        }
+    }
+}
+
+impl<K, V> Deref for MapGuardMut<'_, K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.value
+    }
+}
+
+impl<K, V> DerefMut for MapGuardMut<'_, K, V> {
+    fn deref_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+}
+
+impl<K, V> Drop for MapGuardMut<'_, K, V> {
+    fn drop(&mut self) {
+        // the value may weigh differently now.
+        if let Some(key) = &self.key {
+            self.weights.add(key, &self.value);
+        }
+    }
+}
+
+#[test]
+fn test_update_cow() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    map.insert(1, 1);
+
+    // a concurrent replacement makes the update run again on the new value.
+    let mut calls = 0;
+    let new = map.update_cow(&1, |v| {
+        calls += 1;
+
+        if calls == 1 {
+            map.insert(1, 10);
+        }
+
+        v + 1
+    });
+
+    assert_eq!((Some(11), 2), (new.map(|v| *v), calls));
+    assert!(map.update_cow(&2, |v| v + 1).is_none());
+}
+
+#[test]
+fn test_get_mut() {
+    let map = FixedSizeLruMap::builder(2)
+        .weigher(|_, v: &String| v.len() as u64)
+        .build();
+
+    let (a, _) = map.insert(1, "a".to_owned());
+    map.insert(2, "b".to_owned());
+
+    // a is shared, so it is cloned before being updated.
+    map.get_mut(&1).unwrap().push_str("bc");
+    assert_eq!(("a", "abc"), (&**a, &**map.get(&1).unwrap()));
+    assert_eq!(4, map.weighted_size());
+
+    // 1 was touched by get_mut, so 2 is the one evicted.
+    map.insert(3, "c".to_owned());
+    assert!(map.contains_key(&1) && !map.contains_key(&2));
+    assert!(map.get_mut(&2).is_none());
+}
@@ -0,0 +1,144 @@
+//! Alternative hashers: deterministic for reproducible runs, or faster than
+//! the default SipHash for small keys.
+#[cfg(feature = "fxhash")]
+use std::hash::{BuildHasherDefault, Hasher};
+use std::{collections::hash_map::DefaultHasher, hash::BuildHasher};
+
+/// Builds hashers from a fixed seed instead of a random one, so the iteration
+/// order of a map is the same on every run, see [`FixedSizeLruMap::with_seed`](crate::FixedSizeLruMap::with_seed).
+///
+/// Like [`RandomState`](std::collections::hash_map::RandomState) it uses SipHash,
+/// but with known keys it gives no protection against hash flooding.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SeededState {
+    seed: u64,
+}
+
+impl SeededState {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for SeededState {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> DefaultHasher {
+        let mut hasher = DefaultHasher::new();
+        std::hash::Hasher::write_u64(&mut hasher, self.seed);
+        hasher
+    }
+}
+
+/// Builds [`FxHasher`]s, see [`FastLruMap`](crate::FastLruMap).
+#[cfg(feature = "fxhash")]
+pub type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
+/// The word at a time multiplicative hash used by rustc.
+///
+/// It is several times faster than SipHash on integers and short strings but
+/// offers no protection against hash flooding, so it should only be used when
+/// the keys are not chosen by an attacker.
+#[cfg(feature = "fxhash")]
+#[derive(Clone, Copy, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+#[cfg(feature = "fxhash")]
+impl FxHasher {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+    #[inline]
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(Self::SEED);
+    }
+}
+
+#[cfg(feature = "fxhash")]
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+
+        for chunk in &mut chunks {
+            self.add(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+
+        let rest = chunks.remainder();
+
+        if !rest.is_empty() {
+            let mut word = [0; 8];
+            word[..rest.len()].copy_from_slice(rest);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.add(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[test]
+fn test_seeded_state() {
+    use crate::FixedSizeLruMap;
+
+    let keys = |seed| {
+        let map = FixedSizeLruMap::with_seed(64, seed);
+
+        for i in 0..64 {
+            map.insert(i, ());
+        }
+
+        let keys = map.view().keys().copied().collect::<Vec<_>>();
+        keys
+    };
+
+    assert_eq!(keys(7), keys(7));
+    assert_ne!(keys(7), keys(8));
+    assert_ne!(
+        SeededState::new(1).hash_one(5),
+        SeededState::new(2).hash_one(5)
+    );
+}
+
+#[cfg(feature = "fxhash")]
+#[test]
+fn test_fast_lru_map() {
+    use crate::FastLruMap;
+    use std::hash::BuildHasher;
+
+    let build = FxBuildHasher::default();
+    assert_eq!(build.hash_one("abc"), build.hash_one("abc"));
+    assert_ne!(build.hash_one(1u32), build.hash_one(2u32));
+
+    let map = FastLruMap::with_capacity_and_hasher(2, Default::default());
+    map.insert("a", 1);
+    map.insert("b", 2);
+    map.insert("c", 3);
+    assert!(map.get(&"a").is_none());
+    assert_eq!(3, *map.get(&"c").unwrap());
+}
@@ -0,0 +1,309 @@
+//! An experimental map whose reads never take a lock.
+//!
+//! The entries live in an immutable table that writers copy, modify and swap
+//! in. Readers announce the table they are reading in a hazard pointer so a
+//! writer never frees a table that is still being read.
+use crate::{age, MapGuard};
+use parking_lot::Mutex;
+use std::{
+    collections::hash_map::{HashMap, RandomState},
+    hash::{BuildHasher, Hash},
+    ptr::null_mut,
+    sync::atomic::{
+        AtomicBool, AtomicPtr, AtomicU64,
+        Ordering::{AcqRel, Acquire, Relaxed, Release, SeqCst},
+    },
+};
+
+type Table<K, V, S> = HashMap<K, MapGuard<V>, S>;
+
+/// A fixed size lru map where `get` is lock-free, even during a concurrent eviction.
+///
+/// Every write copies the whole table, so this only pays off for caches that
+/// are read much more often than they are written.
+pub struct HazardLruMap<K, V, S = RandomState> {
+    age: AtomicU64,
+    capacity: usize,
+    hazards: HazardList,
+    retired: Mutex<Vec<*mut Table<K, V, S>>>,
+    table: AtomicPtr<Table<K, V, S>>,
+}
+
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Send for HazardLruMap<K, V, S> {}
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Sync for HazardLruMap<K, V, S> {}
+
+impl<K, V> HazardLruMap<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K, V, S> HazardLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let table = HashMap::with_capacity_and_hasher(capacity + 1, hash_builder);
+
+        HazardLruMap {
+            age: AtomicU64::new(0),
+            capacity,
+            hazards: HazardList::default(),
+            retired: Mutex::new(Vec::new()),
+            table: AtomicPtr::new(Box::into_raw(Box::new(table))),
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.read(|table| table.contains_key(key))
+    }
+
+    pub fn get(&self, key: &K) -> Option<MapGuard<V>> {
+        let guard = self.read(|table| table.get(key).cloned())?;
+        guard.touch(&self.age);
+        Some(guard)
+    }
+
+    pub fn get_or_init<F>(&self, key: K, f: F) -> MapGuard<V>
+    where
+        F: FnOnce() -> V,
+    {
+        match self.get(&key) {
+            Some(value) => value,
+            None => self.insert(key, f()).0,
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) -> (MapGuard<V>, Option<MapGuard<V>>) {
+        self.write(|table| {
+            let mut age = self.age.fetch_add(1, Relaxed);
+
+            if age >= age::REBASE_THRESHOLD {
+                age::rebase(&self.age, table.values().map(|v| &v.0.age));
+                age = self.age.fetch_add(1, Relaxed);
+            }
+
+            let guard = MapGuard::new(age, value);
+            let mut old = table.insert(key, guard.clone());
+
+            if old.is_none() && table.len() > self.capacity {
+                if let Some(key) = table
+                    .iter()
+                    .min_by_key(|(_, v)| v.age())
+                    .map(|(k, _)| k.clone())
+                {
+                    old = table.remove(&key);
+                }
+            }
+
+            (guard, old)
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read(|table| table.is_empty())
+    }
+
+    pub fn len(&self) -> usize {
+        self.read(|table| table.len())
+    }
+
+    pub fn remove(&self, key: &K) -> Option<MapGuard<V>> {
+        if !self.contains_key(key) {
+            return None;
+        }
+
+        self.write(|table| table.remove(key))
+    }
+
+    fn read<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Table<K, V, S>) -> R,
+    {
+        let hazard = self.hazards.acquire();
+        let mut ptr = self.table.load(Acquire);
+
+        // the table may have been retired before the hazard was visible, so
+        // check it is still the current one.
+        loop {
+            hazard.ptr.store(ptr.cast(), SeqCst);
+            let current = self.table.load(SeqCst);
+
+            if current == ptr {
+                break;
+            }
+
+            ptr = current;
+        }
+
+        // SAFETY: the table is protected by the hazard and cannot be freed.
+        let r = f(unsafe { &*ptr });
+        hazard.release();
+        r
+    }
+
+    fn write<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Table<K, V, S>) -> R,
+    {
+        let mut retired = self.retired.lock();
+        let old = self.table.load(Acquire);
+
+        // SAFETY: writers are serialized by the `retired` lock, so `old` is
+        // still the current table and has not been retired.
+        let mut table = unsafe { (*old).clone() };
+        let r = f(&mut table);
+
+        self.table.store(Box::into_raw(Box::new(table)), SeqCst);
+        retired.push(old);
+
+        let protected = self.hazards.protected();
+
+        retired.retain(|&ptr| {
+            if protected.contains(&ptr.cast()) {
+                return true;
+            }
+
+            // SAFETY: the table is unreachable and no reader has announced it.
+            drop(unsafe { Box::from_raw(ptr) });
+            false
+        });
+
+        r
+    }
+}
+
+impl<K, V, S> Drop for HazardLruMap<K, V, S> {
+    fn drop(&mut self) {
+        // SAFETY: having `&mut self`, no reader can be alive.
+        unsafe {
+            drop(Box::from_raw(*self.table.get_mut()));
+
+            for ptr in self.retired.get_mut().drain(..) {
+                drop(Box::from_raw(ptr));
+            }
+        }
+    }
+}
+
+/// A grow-only list of hazard pointers, reused by the readers.
+#[derive(Default)]
+struct HazardList {
+    head: AtomicPtr<Hazard>,
+}
+
+struct Hazard {
+    active: AtomicBool,
+    next: *mut Hazard,
+    ptr: AtomicPtr<()>,
+}
+
+impl HazardList {
+    fn acquire(&self) -> &Hazard {
+        let mut ptr = self.head.load(Acquire);
+
+        // SAFETY: the hazards are only freed when the list is dropped.
+        while let Some(hazard) = unsafe { ptr.as_ref() } {
+            if !hazard.active.load(Relaxed)
+                && hazard
+                    .active
+                    .compare_exchange(false, true, Acquire, Relaxed)
+                    .is_ok()
+            {
+                return hazard;
+            }
+
+            ptr = hazard.next;
+        }
+
+        let hazard = Box::into_raw(Box::new(Hazard {
+            active: AtomicBool::new(true),
+            next: null_mut(),
+            ptr: AtomicPtr::new(null_mut()),
+        }));
+
+        let mut head = self.head.load(Acquire);
+
+        loop {
+            // SAFETY: the hazard is not published yet.
+            unsafe { (*hazard).next = head };
+
+            match self
+                .head
+                .compare_exchange_weak(head, hazard, AcqRel, Acquire)
+            {
+                // SAFETY: same as above, it lives as long as the list.
+                Ok(_) => return unsafe { &*hazard },
+                Err(h) => head = h,
+            }
+        }
+    }
+
+    fn protected(&self) -> Vec<*mut ()> {
+        let mut out = Vec::new();
+        let mut ptr = self.head.load(Acquire);
+
+        // SAFETY: the hazards are only freed when the list is dropped.
+        while let Some(hazard) = unsafe { ptr.as_ref() } {
+            let p = hazard.ptr.load(SeqCst);
+
+            if !p.is_null() {
+                out.push(p);
+            }
+
+            ptr = hazard.next;
+        }
+
+        out
+    }
+}
+
+impl Drop for HazardList {
+    fn drop(&mut self) {
+        let mut ptr = *self.head.get_mut();
+
+        while !ptr.is_null() {
+            // SAFETY: no reader can be alive while the list is dropped.
+            let hazard = unsafe { Box::from_raw(ptr) };
+            ptr = hazard.next;
+        }
+    }
+}
+
+impl Hazard {
+    fn release(&self) {
+        self.ptr.store(null_mut(), Release);
+        self.active.store(false, Release);
+    }
+}
+
+#[test]
+fn test_hazard_concurrent_eviction() {
+    use std::thread::scope;
+
+    let map = HazardLruMap::with_capacity(8);
+
+    scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for i in 0..2000 {
+                    if let Some(v) = map.get(&(i % 16)) {
+                        assert_eq!(i % 16, *v);
+                    }
+                }
+            });
+        }
+
+        s.spawn(|| {
+            for i in 0..2000 {
+                map.insert(i % 16, i % 16);
+            }
+        });
+    });
+
+    assert_eq!(8, map.len());
+}
@@ -0,0 +1,304 @@
+//! Caching of HTTP responses following the freshness rules of their headers.
+//!
+//! # Example
+//! ```
+//! use fixed_size_lru_map::http::{Fetched, HttpCache};
+//!
+//! let cache = HttpCache::with_capacity(100);
+//! let mut requests = 0;
+//!
+//! for _ in 0..2 {
+//!     let body = cache.get_with("https://example.com/a", |stale| {
+//!         requests += 1;
+//!         assert!(stale.is_none());
+//!         Fetched::Response {
+//!             headers: vec![("Cache-Control".into(), "max-age=60".into())],
+//!             response: "hello",
+//!         }
+//!     });
+//!
+//!     assert_eq!("hello", body.unwrap().response);
+//! }
+//!
+//! assert_eq!(1, requests);
+//! ```
+use crate::{FixedSizeLruMap, MapGuard};
+use std::{
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// A cached response with the validators needed to revalidate it.
+pub struct HttpEntry<R> {
+    /// The `ETag` header of the response.
+    pub etag: Option<String>,
+    /// The nanoseconds from the creation of the cache until the entry goes stale.
+    fresh_until: AtomicU64,
+    /// The `Last-Modified` header of the response.
+    pub last_modified: Option<String>,
+    pub response: R,
+}
+
+impl<R> HttpEntry<R> {
+    /// Returns the headers making a request conditional on this entry being outdated.
+    pub fn conditional_headers(&self) -> Vec<(&'static str, &str)> {
+        let etag = self.etag.as_deref().map(|v| ("If-None-Match", v));
+        let modified = self
+            .last_modified
+            .as_deref()
+            .map(|v| ("If-Modified-Since", v));
+        etag.into_iter().chain(modified).collect()
+    }
+}
+
+/// What the origin answered to a request sent by [`HttpCache::get_with`].
+pub enum Fetched<R> {
+    /// A `304 Not Modified`: the stale entry is fresh again under these headers.
+    NotModified { headers: Vec<(String, String)> },
+    /// A full response to cache according to its headers.
+    Response {
+        headers: Vec<(String, String)>,
+        response: R,
+    },
+}
+
+/// The state of a url in an [`HttpCache`].
+pub enum HttpLookup<R> {
+    Fresh(MapGuard<HttpEntry<R>>),
+    Miss,
+    /// The response must be revalidated with the origin before being used, see
+    /// [`conditional_headers`](HttpEntry::conditional_headers).
+    Stale(MapGuard<HttpEntry<R>>),
+}
+
+/// A lru map of HTTP responses keyed by url.
+///
+/// A response stays fresh for the `max-age` of its `Cache-Control` header, less
+/// its `Age`, or else until its `Expires` date. `no-store` responses are not
+/// cached and `no-cache` ones are revalidated on every use. The cache is private:
+/// `s-maxage` and `private` are ignored.
+pub struct HttpCache<R> {
+    epoch: Instant,
+    map: FixedSizeLruMap<String, HttpEntry<R>>,
+}
+
+impl<R> HttpCache<R> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_map(FixedSizeLruMap::with_capacity(capacity))
+    }
+
+    /// Wraps a map configured through its builder.
+    pub fn with_map(map: FixedSizeLruMap<String, HttpEntry<R>>) -> Self {
+        HttpCache {
+            epoch: Instant::now(),
+            map,
+        }
+    }
+
+    /// Returns the response of `url`, fetching it with `fetch` when missing or stale.
+    ///
+    /// `fetch` receives the stale entry, if any, to send a conditional request.
+    /// Returns `None` when the response fetched must not be stored.
+    pub fn get_with<F>(&self, url: &str, fetch: F) -> Option<MapGuard<HttpEntry<R>>>
+    where
+        F: FnOnce(Option<&HttpEntry<R>>) -> Fetched<R>,
+    {
+        let stale = match self.lookup(url) {
+            HttpLookup::Fresh(entry) => return Some(entry),
+            HttpLookup::Miss => None,
+            HttpLookup::Stale(entry) => Some(entry),
+        };
+
+        match fetch(stale.as_deref()) {
+            Fetched::NotModified { headers } => {
+                let stale = stale?;
+                self.revalidated(&stale, &headers);
+                Some(stale)
+            }
+            Fetched::Response { headers, response } => self.insert(url, response, &headers),
+        }
+    }
+
+    /// Caches a response unless its headers forbid it.
+    pub fn insert(
+        &self,
+        url: &str,
+        response: R,
+        headers: &[(String, String)],
+    ) -> Option<MapGuard<HttpEntry<R>>> {
+        let Some(freshness) = freshness(headers, SystemTime::now()) else {
+            self.map.remove(url);
+            return None;
+        };
+
+        let entry = HttpEntry {
+            etag: header(headers, "etag").map(str::to_owned),
+            fresh_until: AtomicU64::new(self.deadline(freshness)),
+            last_modified: header(headers, "last-modified").map(str::to_owned),
+            response,
+        };
+
+        Some(self.map.insert(url.to_owned(), entry).0)
+    }
+
+    pub fn invalidate(&self, url: &str) -> Option<MapGuard<HttpEntry<R>>> {
+        self.map.remove(url)
+    }
+
+    pub fn lookup(&self, url: &str) -> HttpLookup<R> {
+        match self.map.get(url) {
+            Some(entry) if self.elapsed() < entry.fresh_until.load(Relaxed) => {
+                HttpLookup::Fresh(entry)
+            }
+            Some(entry) => HttpLookup::Stale(entry),
+            None => HttpLookup::Miss,
+        }
+    }
+
+    /// Returns the underlying map.
+    pub fn map(&self) -> &FixedSizeLruMap<String, HttpEntry<R>> {
+        &self.map
+    }
+
+    /// Makes a stale entry fresh again after the origin answered `304 Not Modified`.
+    pub fn revalidated(&self, entry: &HttpEntry<R>, headers: &[(String, String)]) {
+        let freshness = freshness(headers, SystemTime::now()).unwrap_or_default();
+        entry.fresh_until.store(self.deadline(freshness), Relaxed);
+    }
+
+    fn deadline(&self, freshness: Duration) -> u64 {
+        self.elapsed().saturating_add(freshness.as_nanos() as u64)
+    }
+
+    fn elapsed(&self) -> u64 {
+        self.epoch.elapsed().as_nanos() as u64
+    }
+}
+
+/// Returns for how long a response stays fresh, or `None` when it must not be stored.
+fn freshness(headers: &[(String, String)], now: SystemTime) -> Option<Duration> {
+    let mut max_age = None;
+    let mut no_cache = false;
+
+    // `no-cache` only applies once every directive is read, so `no-store` wins
+    // wherever it appears.
+    for directive in header(headers, "cache-control").unwrap_or("").split(',') {
+        let (name, value) = directive.split_once('=').unwrap_or((directive, ""));
+
+        match name.trim().to_ascii_lowercase().as_str() {
+            "no-store" => return None,
+            "no-cache" => no_cache = true,
+            "max-age" => max_age = value.trim().trim_matches('"').parse().ok(),
+            _ => {}
+        }
+    }
+
+    if no_cache {
+        return Some(Duration::ZERO);
+    }
+
+    if let Some(max_age) = max_age {
+        let age = header(headers, "age").and_then(|v| v.trim().parse().ok());
+        return Some(
+            Duration::from_secs(max_age).saturating_sub(Duration::from_secs(age.unwrap_or(0))),
+        );
+    }
+
+    let date = header(headers, "date")
+        .and_then(parse_http_date)
+        .unwrap_or(now);
+
+    // an invalid Expires means already expired.
+    let freshness = header(headers, "expires")
+        .and_then(parse_http_date)
+        .and_then(|expires| expires.duration_since(date).ok());
+
+    Some(freshness.unwrap_or_default())
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Parses an IMF-fixdate such as `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn parse_http_date(date: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let [_, day, month, year, time, "GMT"] = date.split_whitespace().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+
+    let day: i64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|&m| m == month)? as i64 + 1;
+    let year: i64 = year.parse().ok()?;
+
+    let mut hms = time.splitn(3, ':').map(|v| v.parse::<i64>().ok());
+    let (h, m, s) = (hms.next()??, hms.next()??, hms.next()??);
+
+    // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let days = era * 146_097 + yoe * 365 + yoe / 4 - yoe / 100 + doy - 719_468;
+
+    let secs = days * 86_400 + h * 3_600 + m * 60 + s;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+#[test]
+fn test_http_freshness() {
+    let headers = |list: &[(&str, &str)]| {
+        list.iter()
+            .map(|&(n, v)| (n.to_owned(), v.to_owned()))
+            .collect::<Vec<_>>()
+    };
+
+    let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+    assert_eq!(
+        Some(UNIX_EPOCH + Duration::from_secs(784_111_777)),
+        parse_http_date(date)
+    );
+
+    let now = SystemTime::now();
+    let fresh = |list: &[(&str, &str)]| freshness(&headers(list), now);
+
+    assert_eq!(None, fresh(&[("Cache-Control", "no-store, max-age=60")]));
+    assert_eq!(None, fresh(&[("Cache-Control", "no-cache, no-store")]));
+    assert_eq!(
+        Some(Duration::ZERO),
+        fresh(&[("cache-control", "no-cache")])
+    );
+    assert_eq!(
+        Some(Duration::from_secs(50)),
+        fresh(&[("Cache-Control", "public, max-age=60"), ("Age", "10")])
+    );
+    assert_eq!(
+        Some(Duration::from_secs(3_600)),
+        fresh(&[("Date", date), ("Expires", "Sun, 06 Nov 1994 09:49:37 GMT")])
+    );
+    assert_eq!(Some(Duration::ZERO), fresh(&[("Expires", "0")]));
+
+    let cache = HttpCache::with_capacity(2);
+    let validators = headers(&[("Cache-Control", "no-cache"), ("ETag", "\"v1\"")]);
+    cache.insert("/a", 1, &validators);
+
+    let revalidated = cache.get_with("/a", |stale| {
+        let stale = stale.unwrap();
+        assert_eq!(
+            vec![("If-None-Match", "\"v1\"")],
+            stale.conditional_headers()
+        );
+        Fetched::NotModified {
+            headers: headers(&[("Cache-Control", "max-age=60")]),
+        }
+    });
+
+    assert_eq!(1, revalidated.unwrap().response);
+    assert!(matches!(cache.lookup("/a"), HttpLookup::Fresh(_)));
+}
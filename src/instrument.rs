@@ -0,0 +1,36 @@
+//! Instrumentation of the slow paths of a map.
+use std::time::Duration;
+
+/// Observes the loads and evictions of a map, e.g. to emit tracing spans.
+///
+/// Every method does nothing by default. The keys are handed as is so the
+/// implementation decides whether formatting them is worth it.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::{FixedSizeLruMap, Instrument};
+/// use std::time::Duration;
+///
+/// struct SlowLoads;
+///
+/// impl Instrument<u32> for SlowLoads {
+///     fn load_finished(&self, key: &u32, elapsed: Duration) {
+///         if elapsed > Duration::from_millis(100) {
+///             eprintln!("slow load of {key}: {elapsed:?}");
+///         }
+///     }
+/// }
+///
+/// let map = FixedSizeLruMap::builder(10).instrument(SlowLoads).build();
+/// map.get_or_init(1, || 'a');
+/// ```
+pub trait Instrument<K>: Send + Sync {
+    /// A missing value of `key` is about to be loaded.
+    fn load_started(&self, _key: &K) {}
+
+    /// The load of `key` has returned after `elapsed`, successfully or not.
+    fn load_finished(&self, _key: &K, _elapsed: Duration) {}
+
+    /// The entry of `key` was evicted to make room for a new one.
+    fn evicted(&self, _key: &K) {}
+}
@@ -0,0 +1,171 @@
+//! Interning of the keys, so the same key material is stored once.
+use parking_lot::Mutex;
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt::{self, Debug, Display, Formatter},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
+
+/// A shared string or byte string, returned by an [`Interner`].
+///
+/// It hashes and compares like its content, so a map keyed by `Interned<str>`
+/// can be queried with a `&str`.
+pub struct Interned<T: ?Sized>(Arc<T>);
+
+impl<T: ?Sized> Interned<T> {
+    /// Returns whether both refer to the same interned value, which is
+    /// always the case for equal values from the same [`Interner`].
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for Interned<T> {
+    fn borrow(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> Clone for Interned<T> {
+    fn clone(&self) -> Self {
+        Interned(Arc::clone(&self.0))
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for Interned<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        (*self.0).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for Interned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized + Display> Display for Interned<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        (*self.0).fmt(f)
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for Interned<T> {}
+
+impl<T: ?Sized + Hash> Hash for Interned<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (*self.0).hash(state)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for Interned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || *self.0 == *other.0
+    }
+}
+
+/// Deduplicates keys such as long urls or paths, across the entries of a map
+/// and across the maps sharing the interner.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::{FixedSizeLruMap, Interned, Interner};
+///
+/// let urls = Interner::<str>::new();
+/// let pages = FixedSizeLruMap::with_capacity(10);
+/// let sizes = FixedSizeLruMap::with_capacity(10);
+///
+/// let url = "https://example.com/a/very/long/path";
+/// pages.insert(urls.intern(url), "<html></html>");
+/// sizes.insert(urls.intern(url), 13);
+///
+/// // both maps share the same copy of the url.
+/// assert!(Interned::ptr_eq(&pages.entries()[0].0, &sizes.entries()[0].0));
+/// assert_eq!(13, *sizes.get(url).unwrap());
+/// ```
+pub struct Interner<T: ?Sized> {
+    /// The number of values after the last purge, to purge again once doubled.
+    purged_len: Mutex<usize>,
+    values: Mutex<HashSet<Interned<T>>>,
+}
+
+impl<T> Interner<T>
+where
+    T: ?Sized + Eq + Hash,
+    for<'a> Arc<T>: From<&'a T>,
+{
+    pub fn new() -> Self {
+        Interner {
+            purged_len: Mutex::new(0),
+            values: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Returns the shared copy of `value`, creating it if needed.
+    pub fn intern(&self, value: &T) -> Interned<T> {
+        let mut values = self.values.lock();
+
+        if let Some(interned) = values.get(value) {
+            return interned.clone();
+        }
+
+        let mut purged_len = self.purged_len.lock();
+
+        // the interner only holds weakly in spirit: the values nobody else
+        // uses are dropped as the set grows.
+        if values.len() >= 2 * (*purged_len).max(64) {
+            values.retain(|v| Arc::strong_count(&v.0) > 1);
+            *purged_len = values.len();
+        }
+
+        let interned = Interned(Arc::from(value));
+        values.insert(interned.clone());
+        interned
+    }
+
+    /// Returns the number of distinct values held, including the ones not used
+    /// anymore since the last purge.
+    pub fn len(&self) -> usize {
+        self.values.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the values used by nobody but the interner.
+    pub fn purge(&self) {
+        let mut values = self.values.lock();
+        values.retain(|v| Arc::strong_count(&v.0) > 1);
+        *self.purged_len.lock() = values.len();
+    }
+}
+
+impl<T> Default for Interner<T>
+where
+    T: ?Sized + Eq + Hash,
+    for<'a> Arc<T>: From<&'a T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_interner() {
+    let interner = Interner::<[u8]>::new();
+    let a = interner.intern(b"abc");
+    let b = interner.intern(b"abc");
+    assert!(Interned::ptr_eq(&a, &b));
+    assert_eq!(1, interner.len());
+
+    drop((a, b));
+    let _c = interner.intern(b"def");
+    interner.purge();
+    assert_eq!(1, interner.len());
+}
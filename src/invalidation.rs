@@ -0,0 +1,116 @@
+//! Invalidation of a key across several maps.
+use crate::FixedSizeLruMap;
+use parking_lot::Mutex;
+use std::{
+    hash::{BuildHasher, Hash},
+    sync::{Arc, Weak},
+};
+
+/// Removes keys from every map that joined it, keeping shards or replicas
+/// coherent after a write to the source of truth.
+///
+/// The bus is cheap to clone, the clones share the joined maps.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::{FixedSizeLruMap, InvalidationBus};
+/// use std::sync::Arc;
+///
+/// let bus = InvalidationBus::new();
+/// let a = Arc::new(FixedSizeLruMap::with_capacity(10));
+/// let b = Arc::new(FixedSizeLruMap::with_capacity(10));
+/// bus.join(&a);
+/// bus.join(&b);
+///
+/// a.insert(1, 'a');
+/// b.insert(1, 'b');
+///
+/// assert_eq!(2, bus.invalidate(&1));
+/// assert!(!a.contains_key(&1) && !b.contains_key(&1));
+/// ```
+pub struct InvalidationBus<K>(Arc<Mutex<Vec<Weak<dyn Invalidate<K>>>>>);
+
+/// A map that can be joined to an [`InvalidationBus`].
+trait Invalidate<K>: Send + Sync {
+    fn invalidate(&self, key: &K) -> bool;
+}
+
+impl<K, V, S> Invalidate<K> for FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash + Send + Sync,
+    V: Send + Sync,
+    S: BuildHasher + Send + Sync,
+{
+    fn invalidate(&self, key: &K) -> bool {
+        self.remove(key).is_some()
+    }
+}
+
+impl<K> InvalidationBus<K> {
+    pub fn new() -> Self {
+        InvalidationBus(Arc::new(Mutex::new(Vec::new())))
+    }
+
+    /// Removes `key` from every joined map, returning how many held it.
+    pub fn invalidate(&self, key: &K) -> usize {
+        let maps = self
+            .0
+            .lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+
+        // removing outside of the bus lock lets a listener publish on the bus.
+        maps.iter().filter(|map| map.invalidate(key)).count()
+    }
+
+    /// Joins `map` to the bus until the map is dropped.
+    pub fn join<V, S>(&self, map: &Arc<FixedSizeLruMap<K, V, S>>)
+    where
+        K: Clone + Eq + Hash + Send + Sync + 'static,
+        V: Send + Sync + 'static,
+        S: BuildHasher + Send + Sync + 'static,
+    {
+        let map: Weak<dyn Invalidate<K>> = Arc::downgrade(map) as Weak<dyn Invalidate<K>>;
+        let mut maps = self.0.lock();
+
+        maps.retain(|m| m.strong_count() > 0);
+        maps.push(map);
+    }
+}
+
+impl<K> Clone for InvalidationBus<K> {
+    fn clone(&self) -> Self {
+        InvalidationBus(Arc::clone(&self.0))
+    }
+}
+
+impl<K> Default for InvalidationBus<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_invalidation_bus() {
+    let bus = InvalidationBus::new();
+    let a = Arc::new(FixedSizeLruMap::with_capacity(10));
+    let b = Arc::new(FixedSizeLruMap::<_, String>::with_capacity(10));
+    bus.clone().join(&a);
+    bus.join(&b);
+
+    a.insert(1, 1);
+    a.insert(2, 2);
+    b.insert(1, "b".to_owned());
+
+    assert_eq!(2, bus.invalidate(&1));
+    assert_eq!(0, bus.invalidate(&1));
+    assert!(a.contains_key(&2));
+
+    drop(a);
+    assert_eq!(0, bus.invalidate(&2));
+    assert_eq!(
+        1,
+        bus.0.lock().iter().filter(|m| m.strong_count() > 0).count()
+    );
+}
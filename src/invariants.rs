@@ -0,0 +1,63 @@
+//! Consistency checks of the internals run after every mutation with the `check-invariants` feature.
+use crate::{FixedSizeLruMap, MapGuard};
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash},
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Panics if `map`, the locked content of the map, is not consistent.
+    ///
+    /// Compiles to nothing without the `check-invariants` feature.
+    #[inline]
+    pub(crate) fn check_invariants(&self, map: &HashMap<K, MapGuard<V>, S>) {
+        #[cfg(feature = "check-invariants")]
+        {
+            use std::{collections::HashSet, sync::atomic::Ordering::Relaxed};
+
+            assert!(
+                map.len() <= self.capacity,
+                "{} entries in a map of capacity {}",
+                map.len(),
+                self.capacity
+            );
+
+            // the recency order is given by the ages, they must be distinct and
+            // all taken from the counter.
+            let counter = self.age.load(Relaxed);
+            let mut ages = HashSet::with_capacity(map.len());
+
+            for guard in map.values() {
+                let age = guard.age();
+                assert!(
+                    age < counter,
+                    "age {age} not yet given by the counter {counter}"
+                );
+                assert!(ages.insert(age), "age {age} shared by two entries");
+            }
+        }
+
+        #[cfg(not(feature = "check-invariants"))]
+        let _ = map;
+    }
+}
+
+#[cfg(feature = "check-invariants")]
+#[test]
+#[should_panic(expected = "shared by two entries")]
+fn test_check_invariants() {
+    let map = FixedSizeLruMap::with_capacity(3);
+    map.insert(1, 1);
+    map.insert(2, 2);
+
+    let entries = map.map.read();
+    entries[&2]
+        .0
+        .age
+        .store(entries[&1].age(), std::sync::atomic::Ordering::Relaxed);
+    map.check_invariants(&entries);
+}
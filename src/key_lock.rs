@@ -0,0 +1,121 @@
+//! Per-key async locks.
+use crate::FixedSizeLruMap;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    future::poll_fn,
+    hash::{BuildHasher, Hash},
+    task::{Poll, Waker},
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Locks `key` until the returned guard is dropped.
+    ///
+    /// Waiting never blocks the thread nor the map, so this can serialize
+    /// read-modify-write sequences on a key from async code.
+    ///
+    /// The lock is advisory: it only excludes the other callers of
+    /// `lock_key_advisory`. The other methods of the map, including the loads
+    /// of `get_or_init_async`, ignore it, so a value can still be loaded or
+    /// replaced while its key is locked.
+    pub async fn lock_key_advisory(&self, key: K) -> KeyGuard<'_, K> {
+        poll_fn(|cx| {
+            let mut locks = self.key_locks.0.lock();
+
+            match locks.get_mut(&key) {
+                Some(waiters) => {
+                    if !waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                        waiters.push(cx.waker().clone());
+                    }
+
+                    Poll::Pending
+                }
+                None => {
+                    locks.insert(key.clone(), Vec::new());
+                    Poll::Ready(())
+                }
+            }
+        })
+        .await;
+
+        KeyGuard {
+            key,
+            locks: &self.key_locks,
+        }
+    }
+}
+
+/// The locked keys with the tasks waiting on them.
+pub(crate) struct KeyLocks<K>(Mutex<HashMap<K, Vec<Waker>>>);
+
+impl<K> Default for KeyLocks<K> {
+    fn default() -> Self {
+        KeyLocks(Mutex::new(HashMap::new()))
+    }
+}
+
+/// Unlocks a key locked with [`FixedSizeLruMap::lock_key_advisory`] when dropped.
+pub struct KeyGuard<'a, K>
+where
+    K: Eq + Hash,
+{
+    key: K,
+    locks: &'a KeyLocks<K>,
+}
+
+impl<K> KeyGuard<'_, K>
+where
+    K: Eq + Hash,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K> Drop for KeyGuard<'_, K>
+where
+    K: Eq + Hash,
+{
+    fn drop(&mut self) {
+        let waiters = self.locks.0.lock().remove(&self.key);
+
+        // every waiter competes again, so a cancelled one cannot lose the wake up.
+        for waker in waiters.into_iter().flatten() {
+            waker.wake();
+        }
+    }
+}
+
+#[test]
+fn test_lock_key() {
+    use crate::future::block_on;
+    use std::{
+        sync::atomic::{AtomicBool, Ordering::SeqCst},
+        thread::{scope, sleep},
+        time::Duration,
+    };
+
+    let map = FixedSizeLruMap::<_, ()>::with_capacity(2);
+    let held = AtomicBool::new(false);
+
+    scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                block_on(async {
+                    let _guard = map.lock_key_advisory(1).await;
+                    assert!(!held.swap(true, SeqCst));
+                    sleep(Duration::from_millis(5));
+                    held.store(false, SeqCst);
+                })
+            });
+        }
+    });
+
+    // other keys are independent.
+    let _a = block_on(map.lock_key_advisory(1));
+    let _b = block_on(map.lock_key_advisory(2));
+}
@@ -12,21 +12,212 @@
 //! assert_eq!(10, b);
 //! assert_eq!(1, map.len());
 //! ```
-use parking_lot::RwLock;
+//!
+//! # Features
+//! - `check-invariants`: asserts the consistency of the internals after every mutation, to catch regressions.
+//! - `compression`: [`Compressed`] values, kept compressed in memory and decompressed on access.
+//! - `ffi`: a C interface over byte string keys and values, see [`ffi`].
+//! - `fxhash`: [`FastLruMap`], hashing the keys with the fast but unkeyed [`FxHasher`].
+//! - `hazard`: experimental [`HazardLruMap`] where reads never take a lock.
+//! - `http`: an [`http::HttpCache`] of responses honoring their `Cache-Control` and `Expires` headers.
+//! - `lock-stats`: counts lock acquisitions and contention, see [`FixedSizeLruMap::lock_stats`].
+//! - `macros`: [`lru_memoize!`], memoizing the calls to a function in a map with no boilerplate.
+//! - `redis`: [`RedisTier`], a [`RemoteTier`] sharing the entries of several processes through Redis.
+//! - `stats`: counts hits, misses, insertions and evictions, see [`FixedSizeLruMap::stats`].
+//! - `std` (default): the helpers needing files, sockets or threads: persistence,
+//!   write-ahead log, spilling, background maintenance and refreshes, `http` and `redis`.
+//! - `trace`: records the accesses in a ring buffer, see [`FixedSizeLruMap::trace`].
+//!
+//! # WebAssembly
+//! The map runs single threaded on `wasm32-unknown-unknown`, which has no clock:
+//! it only reads the clock for the [`timestamps`](FixedSizeLruMapBuilder::timestamps),
+//! [`refresh_after`](FixedSizeLruMapBuilder::refresh_after),
+//! [`negative_ttl`](FixedSizeLruMapBuilder::negative_ttl), the load timings of an
+//! [`Instrument`] and the `lock-stats`, `stats` and `trace` features. Leave them
+//! off there, along with the helpers spawning threads.
+mod age;
+mod array;
+mod builder;
+#[cfg(feature = "compression")]
+mod compress;
+mod counter;
+mod deps;
+mod equivalent;
+mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod future;
+mod group;
+mod guard_mut;
+mod hash;
+#[cfg(feature = "hazard")]
+mod hazard;
+#[cfg(feature = "http")]
+pub mod http;
+mod instrument;
+mod intern;
+mod invalidation;
+mod invariants;
+mod key_lock;
+mod loader;
+mod lock;
+mod maintenance;
+mod mapped;
+mod memoize;
+mod metrics;
+pub mod moka;
+mod multimap;
+mod negative;
+mod parallel;
+#[cfg(feature = "std")]
+mod persist;
+mod pool;
+mod queue;
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "std")]
+mod refresh;
+mod registry;
+mod remote;
+mod report;
+mod retry;
+mod segmented;
+mod semaphore;
+mod set;
+pub mod simulate;
+mod slab;
+mod snapshot;
+#[cfg(feature = "std")]
+mod spill;
+mod stats;
+mod store;
+mod tags;
+mod tenant;
+mod tiered;
+mod timeout;
+mod trace;
+mod typed;
+mod view;
+#[cfg(feature = "std")]
+mod wal;
+mod watch;
+mod weak;
+mod weight;
+
+pub use array::{ArrayLruMap, SmallLruMap, SmallRef};
+pub use builder::FixedSizeLruMapBuilder;
+#[cfg(feature = "compression")]
+pub use compress::Compressed;
+pub use counter::FixedSizeLruCounter;
+pub use equivalent::{Equivalent, Pair};
+pub use events::{CacheEvent, EventReceiver, RemovalCause};
+pub use group::{CacheGroup, Namespace};
+pub use guard_mut::MapGuardMut;
+pub use hash::SeededState;
+#[cfg(feature = "fxhash")]
+pub use hash::{FxBuildHasher, FxHasher};
+#[cfg(feature = "hazard")]
+pub use hazard::HazardLruMap;
+pub use instrument::Instrument;
+pub use intern::{Interned, Interner};
+pub use invalidation::InvalidationBus;
+pub use key_lock::KeyGuard;
+pub use loader::{AsyncCacheLoader, CacheLoader, ReadThroughCache};
+#[cfg(feature = "lock-stats")]
+pub use lock::LockStats;
+#[cfg(feature = "std")]
+pub use maintenance::MaintenanceHandle;
+pub use mapped::MappedMapGuard;
+pub use memoize::{Memoize, MemoizeExt};
+pub use metrics::{render_prometheus, Metric, MetricKind};
+pub use multimap::LruMultiMap;
+#[cfg(feature = "std")]
+pub use persist::{Persist, SnapshotReader};
+pub use queue::LruQueue;
+#[cfg(feature = "redis")]
+pub use redis::RedisTier;
+pub use registry::{registered_caches, RegisteredCache};
+pub use remote::{RemoteTier, RemoteTieredCache};
+pub use report::CacheReport;
+pub use retry::RetryPolicy;
+pub use segmented::SegmentedLruMap;
+pub use set::FixedSizeLruSet;
+pub use slab::{SlabHandle, SlabLruMap};
+pub use snapshot::CacheSnapshot;
+#[cfg(feature = "std")]
+pub use spill::SpillCache;
+#[cfg(feature = "stats")]
+pub use stats::CacheStats;
+pub use store::{Write, WriteBehindCache, WriteSink, WriteStore, WriteThroughCache};
+pub use tenant::MultiTenantLruMap;
+pub use tiered::TieredLruMap;
+pub use timeout::Timeout;
+pub use trace::{write_trace, Access, AccessOp};
+pub use typed::TypedLruCache;
+pub use view::MapView;
+#[cfg(feature = "std")]
+pub use wal::WalCache;
+pub use weak::WeakLruMap;
+
+use events::Notice;
+use lock::Lock;
+use pool::EntryPool;
+#[cfg(feature = "std")]
+use std::sync::atomic::AtomicBool;
 use std::{
+    borrow::Borrow,
     collections::hash_map::{HashMap, RandomState},
+    fmt::{self, Debug, Display, Formatter},
     hash::{BuildHasher, Hash, Hasher},
     ops::Deref,
     sync::{
         atomic::{AtomicU64, Ordering::Relaxed},
-        Arc,
+        Arc, OnceLock, Weak,
     },
+    time::{Duration, Instant},
 };
 
+/// A [`FixedSizeLruMap`] hashing its keys with [`FxHasher`] instead of SipHash.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::FastLruMap;
+///
+/// let map = FastLruMap::with_capacity_and_hasher(2, Default::default());
+/// map.insert(1, "a");
+/// assert_eq!("a", *map.get(&1).unwrap());
+/// ```
+#[cfg(feature = "fxhash")]
+pub type FastLruMap<K, V> = FixedSizeLruMap<K, V, FxBuildHasher>;
+
 pub struct FixedSizeLruMap<K, V, S = RandomState> {
     age: AtomicU64,
     capacity: usize,
-    map: RwLock<HashMap<K, MapGuard<V>, S>>,
+    dependencies: deps::Dependencies<K>,
+    events: events::Subscribers<CacheEvent<K, V>>,
+    evictions: events::Subscribers<(K, MapGuard<V>)>,
+    insert_hook: Option<events::InsertHook<K, V>>,
+    instrument: Option<Box<dyn Instrument<K>>>,
+    key_locks: key_lock::KeyLocks<K>,
+    loaders: Option<semaphore::Semaphore>,
+    loads: future::Loads<K, V>,
+    map: Lock<HashMap<K, MapGuard<V>, S>>,
+    negatives: negative::Negatives<K>,
+    /// Tells the listeners of an explicit removal. Publishing clones the key, so
+    /// this is set by the first insertion, which requires `K: Clone`, letting
+    /// `remove` do without that bound.
+    notify_removed: OnceLock<fn(&Self, &K, MapGuard<V>)>,
+    pool: Option<EntryPool<V>>,
+    refresh_after: Option<Duration>,
+    removal_listener: Option<events::RemovalListener<K, V>>,
+    stats: stats::StatsCounters,
+    tags: tags::Tags<K, V>,
+    /// Whether the entries record when they were inserted and last read.
+    timestamps: bool,
+    trace: trace::TraceRecorder,
+    update_hook: Option<events::UpdateHook<K, V>>,
+    watchers: watch::Watchers<K, V>,
+    weights: weight::Weights<K, V>,
 }
 
 impl<K, V> FixedSizeLruMap<K, V>
@@ -36,6 +227,16 @@ where
     pub fn with_capacity(capacity: usize) -> FixedSizeLruMap<K, V> {
         Self::with_capacity_and_hasher(capacity, Default::default())
     }
+
+    pub fn builder(capacity: usize) -> FixedSizeLruMapBuilder<K, V> {
+        FixedSizeLruMapBuilder::new(capacity)
+    }
+
+    /// Creates a map hashing its keys with a [`SeededState`], so its iteration
+    /// order is reproducible from one run to the next, e.g. in tests and simulations.
+    pub fn with_seed(capacity: usize, seed: u64) -> FixedSizeLruMap<K, V, SeededState> {
+        FixedSizeLruMap::with_capacity_and_hasher(capacity, SeededState::new(seed))
+    }
 }
 
 impl<K, V, S> FixedSizeLruMap<K, V, S>
@@ -47,20 +248,124 @@ where
         FixedSizeLruMap {
             age: AtomicU64::new(0),
             capacity,
-            map: RwLock::from(HashMap::with_capacity_and_hasher(
+            dependencies: Default::default(),
+            events: Default::default(),
+            evictions: Default::default(),
+            insert_hook: None,
+            instrument: None,
+            key_locks: Default::default(),
+            loaders: None,
+            loads: Default::default(),
+            map: Lock::new(HashMap::with_capacity_and_hasher(
                 capacity + 1,
                 hash_builder,
             )),
+            negatives: Default::default(),
+            notify_removed: OnceLock::new(),
+            pool: None,
+            refresh_after: None,
+            removal_listener: None,
+            stats: Default::default(),
+            tags: Default::default(),
+            timestamps: false,
+            trace: Default::default(),
+            update_hook: None,
+            watchers: Default::default(),
+            weights: Default::default(),
         }
     }
 
-    pub fn contains_key(&self, key: &K) -> bool {
-        self.map.read().contains_key(key)
+    /// Returns the maximum number of entries of the map.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Removes every entry, telling the listeners of each removal.
+    ///
+    /// The dependencies between the keys are dropped along with the entries.
+    pub fn clear(&self) {
+        let removed = {
+            let mut map = self.map.write();
+            let removed = map.drain().collect::<Vec<_>>();
+            self.check_invariants(&map);
+            removed
+        };
+
+        self.dependencies.clear();
+
+        for (key, old) in removed {
+            self.weights.sub(&key, &old);
+            self.stats.removal(RemovalCause::Explicit);
+            self.retire(Some(&old));
+
+            if self.has_listeners() {
+                if let Some(notify) = self.notify_removed.get() {
+                    notify(self, &key, old);
+                }
+            }
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q::Key>,
+        Q: Equivalent<K> + ?Sized,
+    {
+        self.map.read().contains_key(key.as_key())
+    }
+
+    /// Returns a snapshot of the entries, in no particular order.
+    pub fn entries(&self) -> Vec<(K, MapGuard<V>)>
+    where
+        K: Clone,
+    {
+        self.map
+            .read()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    /// Subscribes to every mutation of the map.
+    pub fn events(&self) -> EventReceiver<CacheEvent<K, V>> {
+        self.events.subscribe()
     }
 
-    pub fn get(&self, key: &K) -> Option<MapGuard<V>> {
+    /// Subscribes to the entries evicted to make room for new ones.
+    pub fn evictions(&self) -> EventReceiver<(K, MapGuard<V>)> {
+        self.evictions.subscribe()
+    }
+
+    /// Subscribes to every mutation of the map, keeping only the last `capacity`
+    /// events when the receiver falls behind.
+    ///
+    /// Unlike [`events`](Self::events), a slow observer cannot make the queue grow
+    /// without bound: the oldest events are discarded and counted by
+    /// [`dropped`](EventReceiver::dropped).
+    pub fn subscribe(&self, capacity: usize) -> EventReceiver<CacheEvent<K, V>> {
+        self.events.subscribe_bounded(capacity.max(1))
+    }
+
+    /// Returns the value of `key`, which can be looked up by any [`Equivalent`]
+    /// query, e.g. a `&str` for `String` keys.
+    pub fn get<Q>(&self, key: &Q) -> Option<MapGuard<V>>
+    where
+        K: Borrow<Q::Key>,
+        Q: Equivalent<K> + ?Sized,
+    {
         let map = self.map.read();
-        let guard = map.get(key)?;
+        let key = key.as_key();
+
+        let Some(guard) = map.get(key) else {
+            self.stats.miss();
+            self.trace
+                .record(AccessOp::Miss, || map.hasher().hash_one(key));
+            return None;
+        };
+
+        self.stats.hit();
+        self.trace
+            .record(AccessOp::Hit, || map.hasher().hash_one(key));
         self.update_guard_age(guard);
         Some(MapGuard::clone(guard))
     }
@@ -72,67 +377,590 @@ where
     {
         match self.get(&key) {
             Some(value) => value,
-            None => self.insert(key, f()).0,
+            None => {
+                let value = self.load(&key, f);
+                self.insert(key, value).0
+            }
+        }
+    }
+
+    /// Returns the `n` entries read the most since they were inserted, the hottest first.
+    pub fn hottest(&self, n: usize) -> Vec<(K, MapGuard<V>)>
+    where
+        K: Clone,
+    {
+        let mut entries = self.entries();
+        let by_heat = |(_, v): &(K, MapGuard<V>)| std::cmp::Reverse((MapGuard::hits(v), v.age()));
+
+        if n < entries.len() {
+            entries.select_nth_unstable_by_key(n, by_heat);
+            entries.truncate(n);
         }
+
+        entries.sort_unstable_by_key(by_heat);
+        entries
     }
 
     pub fn insert(&self, key: K, value: V) -> (MapGuard<V>, Option<MapGuard<V>>)
     where
         K: Clone,
     {
-        let mut map = self.map.write();
-        let age = self.age.fetch_add(1, Relaxed);
-        let guard = MapGuard(Arc::new((AtomicU64::new(age), value)));
+        self.insert_arc(key, Arc::new(value))
+    }
+
+    /// Inserts a value already shared elsewhere, the map keeps the same `Arc`
+    /// instead of copying the value.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::{FixedSizeLruMap, MapGuard};
+    /// use std::sync::Arc;
+    ///
+    /// let blob = Arc::new(vec![0u8; 1024]);
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// let (guard, _) = map.insert_arc("blob", Arc::clone(&blob));
+    /// assert!(Arc::ptr_eq(&blob, &MapGuard::into_arc(guard)));
+    /// ```
+    pub fn insert_arc(&self, key: K, value: Arc<V>) -> (MapGuard<V>, Option<MapGuard<V>>)
+    where
+        K: Clone,
+    {
+        let mut notices = Vec::new();
+        let inserted = self.insert_locked(&mut self.map.write(), key, value, &mut notices);
+        self.notify(notices);
+        inserted
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.read().is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.read().len()
+    }
+
+    /// Returns the usage counters of the internal lock.
+    #[cfg(feature = "lock-stats")]
+    pub fn lock_stats(&self) -> LockStats {
+        self.map.stats()
+    }
+
+    /// Returns the usage counters of the map.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> CacheStats {
+        self.stats.snapshot()
+    }
+
+    /// Returns what has been counted since `snapshot` was taken with [`stats`](Self::stats).
+    #[cfg(feature = "stats")]
+    pub fn stats_delta_since(&self, snapshot: &CacheStats) -> CacheStats {
+        self.stats().delta_since(snapshot)
+    }
+
+    /// Sets the usage counters back to zero, returning their previous values.
+    #[cfg(feature = "stats")]
+    pub fn stats_reset(&self) -> CacheStats {
+        self.stats.reset()
+    }
+
+    /// Returns the recorded accesses, the oldest first, see
+    /// [`trace`](FixedSizeLruMapBuilder::trace).
+    #[cfg(feature = "trace")]
+    pub fn trace(&self) -> Vec<Access> {
+        self.trace.snapshot()
+    }
+
+    /// Returns the ratio of the lookups that found a value over the recent
+    /// window, see [`hit_ratio_window`](FixedSizeLruMapBuilder::hit_ratio_window).
+    #[cfg(feature = "stats")]
+    pub fn windowed_hit_ratio(&self) -> f64 {
+        self.stats.windowed_hit_ratio()
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<MapGuard<V>>
+    where
+        K: Borrow<Q::Key>,
+        Q: Equivalent<K> + ?Sized,
+    {
+        self.remove_if(key, |_| true)
+    }
+
+    /// Removes `key` only if `f` accepts its current value, checked under the
+    /// lock so the value cannot be replaced in between.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::{FixedSizeLruMap, MapGuard};
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// let (old, _) = map.insert("a", 1);
+    /// map.insert("a", 2);
+    ///
+    /// // the value read earlier is outdated, it is not the one removed.
+    /// assert!(map.remove_if(&"a", |v| MapGuard::ptr_eq(v, &old)).is_none());
+    /// assert_eq!(2, *map.remove_if(&"a", |v| **v == 2).unwrap());
+    /// ```
+    pub fn remove_if<Q, F>(&self, key: &Q, f: F) -> Option<MapGuard<V>>
+    where
+        F: FnOnce(&MapGuard<V>) -> bool,
+        K: Borrow<Q::Key>,
+        Q: Equivalent<K> + ?Sized,
+    {
+        let key = key.as_key();
+
+        let removed = {
+            let mut map = self.map.write();
+            self.trace
+                .record(AccessOp::Remove, || map.hasher().hash_one(key));
+
+            let removed = match map.get(key) {
+                Some(v) if f(v) => map.remove_entry(key),
+                _ => None,
+            };
+
+            self.check_invariants(&map);
+            removed
+        };
+
+        let (removed_key, old) = removed?;
+        self.weights.sub(&removed_key, &old);
+        self.stats.removal(RemovalCause::Explicit);
+
+        if self.has_listeners() {
+            if let Some(notify) = self.notify_removed.get() {
+                notify(self, &removed_key, old.clone());
+            }
+        }
+
+        self.retire(Some(&old));
+        self.remove_dependents(&removed_key);
+        Some(old)
+    }
+
+    /// Returns whether anyone must be told of the changes of the map.
+    fn has_listeners(&self) -> bool {
+        !self.events.is_empty()
+            || !self.evictions.is_empty()
+            || self.insert_hook.is_some()
+            || self.instrument.is_some()
+            || self.removal_listener.is_some()
+            || self.update_hook.is_some()
+            || !self.watchers.is_empty()
+    }
+
+    /// Inserts the value in the locked `map`, queueing in `notices` what must be
+    /// told to the listeners with [`notify`](Self::notify) once the lock is released.
+    fn insert_locked(
+        &self,
+        map: &mut HashMap<K, MapGuard<V>, S>,
+        key: K,
+        value: Arc<V>,
+        notices: &mut Vec<Notice<K, V>>,
+    ) -> (MapGuard<V>, Option<MapGuard<V>>)
+    where
+        K: Clone,
+    {
+        self.notify_removed.get_or_init(|| {
+            |map, key, old| {
+                map.notify(vec![Notice::Removed(
+                    key.clone(),
+                    old,
+                    RemovalCause::Explicit,
+                )])
+            }
+        });
+
+        let mut age = self.age.fetch_add(1, Relaxed);
+
+        if age >= age::REBASE_THRESHOLD {
+            age::rebase(&self.age, map.values().map(|v| &v.0.age));
+            age = self.age.fetch_add(1, Relaxed);
+        }
+
+        let mut guard = match &self.pool {
+            Some(pool) => pool.guard(age, value),
+            None => MapGuard::from_arc(age, value),
+        };
+
+        if self.timestamps {
+            guard.stamp();
+        }
+
+        self.negatives.remove(&key);
+        self.stats.insertion();
+        self.trace
+            .record(AccessOp::Insert, || map.hasher().hash_one(&key));
+
+        if self.weights.enabled() {
+            self.weights.add(&key, &guard);
+
+            if let Some(cur) = map.get(&key) {
+                self.weights.sub(&key, cur);
+            }
+        }
+
+        let listened = self.has_listeners();
+        let key_copy = listened.then(|| key.clone());
         let mut old = map.insert(key, guard.clone());
 
-        if old.is_none() && map.len() > self.capacity {
-            if let Some(key) = map
+        if old.is_some() {
+            self.stats.removal(RemovalCause::Replaced);
+        }
+
+        if let Some(key) = key_copy {
+            notices.push(match &old {
+                Some(old) => Notice::Updated(key, old.clone(), guard.clone()),
+                None => Notice::Inserted(key, guard.clone()),
+            });
+        }
+
+        // the new entry is the most recent, it is only evicted by a zero capacity.
+        while map.len() > self.capacity || (self.weights.over_budget() && map.len() > 1) {
+            let Some(key) = map
                 .iter()
                 .min_by_key(|(_, v)| v.age())
                 .map(|(k, _)| k.clone())
-            {
-                old = map.remove(&key);
+            else {
+                break;
+            };
+
+            let Some(evicted) = map.remove(&key) else {
+                break;
+            };
+
+            self.weights.sub(&key, &evicted);
+            self.stats.removal(RemovalCause::Capacity);
+
+            if listened {
+                notices.push(Notice::Removed(
+                    key,
+                    evicted.clone(),
+                    RemovalCause::Capacity,
+                ));
+            }
+
+            // only the replaced or the first evicted value is returned.
+            match old {
+                Some(_) => self.retire(Some(&evicted)),
+                None => old = Some(evicted),
             }
         }
 
+        self.check_invariants(map);
+        self.retire(old.as_ref());
         (guard, old)
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.map.read().is_empty()
+    /// Calls `f` to load the value of `key`, waiting first for a permit when the
+    /// concurrent loads are limited.
+    pub(crate) fn load<F, R>(&self, key: &K, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let _permit = self.loaders.as_ref().map(|s| s.acquire());
+
+        let Some(instrument) = &self.instrument else {
+            return f();
+        };
+
+        instrument.load_started(key);
+        let start = Instant::now();
+        let r = f();
+        instrument.load_finished(key, start.elapsed());
+        r
     }
 
-    pub fn len(&self) -> usize {
-        self.map.read().len()
+    /// Tells the listeners of the changes queued under the lock, in order.
+    ///
+    /// Must be called once the lock is released, so a listener calling back
+    /// into the map cannot deadlock.
+    pub(crate) fn notify(&self, notices: Vec<Notice<K, V>>)
+    where
+        K: Clone,
+    {
+        for notice in notices {
+            match notice {
+                Notice::Inserted(key, new) => {
+                    self.publish(|| CacheEvent::Insert(key.clone(), new.clone()));
+
+                    if let Some(hook) = &self.insert_hook {
+                        hook(&key, &new);
+                    }
+                }
+                Notice::Removed(key, old, cause) => {
+                    if let RemovalCause::Capacity = cause {
+                        if let Some(instrument) = &self.instrument {
+                            instrument.evicted(&key);
+                        }
+
+                        self.publish(|| CacheEvent::Evict(key.clone(), old.clone()));
+                        self.evictions.send(|| (key.clone(), old.clone()));
+                    } else {
+                        self.publish(|| CacheEvent::Remove(key.clone(), old.clone()));
+                    }
+
+                    if let Some(listener) = &self.removal_listener {
+                        listener(key, old, cause);
+                    }
+                }
+                Notice::Updated(key, old, new) => {
+                    self.publish(|| CacheEvent::Update {
+                        key: key.clone(),
+                        old: old.clone(),
+                        new: new.clone(),
+                    });
+
+                    if let Some(hook) = &self.update_hook {
+                        hook(&key, &old, &new);
+                    }
+
+                    if let Some(listener) = &self.removal_listener {
+                        listener(key, old, RemovalCause::Replaced);
+                    }
+                }
+            }
+        }
     }
 
-    pub fn remove(&self, key: &K) -> Option<MapGuard<V>> {
-        self.map.write().remove(key)
+    /// Sends the event built by `f` to the subscribers and to the watchers of its key.
+    fn publish<F>(&self, f: F)
+    where
+        F: FnOnce() -> CacheEvent<K, V>,
+        K: Clone,
+    {
+        if self.watchers.is_empty() {
+            return self.events.send(f);
+        }
+
+        let event = f();
+        self.watchers.send(&event);
+        self.events.send(|| event);
+    }
+
+    fn retire(&self, guard: Option<&MapGuard<V>>) {
+        if let (Some(pool), Some(guard)) = (&self.pool, guard) {
+            pool.retire(guard);
+        }
     }
 
     fn update_guard_age(&self, guard: &MapGuard<V>) {
-        let v = self.age.fetch_add(1, Relaxed);
-        guard.set_age(v);
+        guard.touch(&self.age);
     }
 }
 
-pub struct MapGuard<V>(Arc<(AtomicU64, V)>);
+pub struct MapGuard<V>(Arc<Entry<V>>);
+
+struct Entry<V> {
+    /// Nanoseconds between `loaded` and the last read, zero before the first one.
+    accessed: AtomicU64,
+    age: AtomicU64,
+    hits: AtomicU64,
+    /// When the value was inserted, only recorded by a map with timestamps.
+    loaded: Option<Instant>,
+    #[cfg(feature = "std")]
+    refreshing: AtomicBool,
+    /// Behind its own `Arc` so it can be shared with APIs expecting an `Arc<V>`.
+    value: Arc<V>,
+}
+
+impl<V> Entry<V> {
+    fn new(age: u64, value: Arc<V>) -> Self {
+        Entry {
+            accessed: AtomicU64::new(0),
+            age: AtomicU64::new(age),
+            hits: AtomicU64::new(0),
+            loaded: None,
+            #[cfg(feature = "std")]
+            refreshing: AtomicBool::new(false),
+            value,
+        }
+    }
+}
 
 impl<V> MapGuard<V> {
+    fn new(age: u64, value: V) -> Self {
+        Self::from_arc(age, Arc::new(value))
+    }
+
+    fn from_arc(age: u64, value: Arc<V>) -> Self {
+        MapGuard(Arc::new(Entry::new(age, value)))
+    }
+
     fn age(&self) -> u64 {
-        (self.0).0.load(Relaxed)
+        self.0.age.load(Relaxed)
+    }
+
+    /// Returns a weak handle to the entry, which does not keep the value alive.
+    ///
+    /// With an [`entry_pool`](FixedSizeLruMapBuilder::entry_pool), an evicted
+    /// entry stays reachable until it leaves the pool.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::{FixedSizeLruMap, MapGuard};
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(1);
+    /// let (guard, _) = map.insert(1, 'a');
+    /// let weak = MapGuard::downgrade(&guard);
+    /// drop(guard);
+    /// assert_eq!(Some('a'), weak.upgrade().map(|v| *v));
+    ///
+    /// map.insert(2, 'b');
+    /// assert!(weak.upgrade().is_none());
+    /// ```
+    pub fn downgrade(this: &MapGuard<V>) -> WeakMapGuard<V> {
+        WeakMapGuard(Arc::downgrade(&this.0))
+    }
+
+    /// Converts the guard to a pointer, to be converted back with [`from_raw`](Self::from_raw).
+    #[cfg(feature = "ffi")]
+    pub(crate) fn into_raw(this: MapGuard<V>) -> *const () {
+        Arc::into_raw(this.0).cast()
+    }
+
+    /// # Safety
+    /// `ptr` must come from [`into_raw`](Self::into_raw) with the same `V`, and
+    /// not be converted back more than once.
+    #[cfg(feature = "ffi")]
+    pub(crate) unsafe fn from_raw(ptr: *const ()) -> MapGuard<V> {
+        MapGuard(Arc::from_raw(ptr.cast()))
+    }
+
+    /// Returns how many times the value has been read from the map since it was inserted.
+    pub fn hits(this: &MapGuard<V>) -> u64 {
+        this.0.hits.load(Relaxed)
+    }
+
+    /// Returns when the entry was inserted, when it was last read and how many times.
+    ///
+    /// The times are only recorded by a map built with
+    /// [`timestamps`](FixedSizeLruMapBuilder::timestamps).
+    pub fn info(this: &MapGuard<V>) -> EntryInfo {
+        let accessed = Duration::from_nanos(this.0.accessed.load(Relaxed));
+
+        EntryInfo {
+            created_at: this.0.loaded,
+            hits: Self::hits(this),
+            last_accessed_at: this.0.loaded.map(|loaded| loaded + accessed),
+        }
+    }
+
+    /// Records the insertion time of an entry not shared yet.
+    fn stamp(&mut self) {
+        if let Some(entry) = Arc::get_mut(&mut self.0) {
+            entry.loaded = Some(Instant::now());
+        }
+    }
+
+    /// Records a read of the value, taking its new age from `counter`.
+    fn touch(&self, counter: &AtomicU64) {
+        // without timestamps, a read does not look at the clock at all.
+        if let Some(loaded) = self.0.loaded {
+            let accessed = loaded.elapsed().as_nanos().try_into().unwrap_or(u64::MAX);
+            self.0.accessed.store(accessed, Relaxed);
+        }
+
+        age::touch(counter, &self.0.age);
+        self.0.hits.fetch_add(1, Relaxed);
     }
 
-    fn set_age(&self, value: u64) {
-        (self.0).0.store(value, Relaxed);
+    /// Returns the shared value, without copying it.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::{FixedSizeLruMap, MapGuard};
+    /// use std::sync::Arc;
+    ///
+    /// fn render(page: Arc<String>) -> usize {
+    ///     page.len()
+    /// }
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// let (page, _) = map.insert("/", "<html></html>".to_owned());
+    /// assert_eq!(13, render(MapGuard::into_arc(page)));
+    /// ```
+    pub fn into_arc(this: MapGuard<V>) -> Arc<V> {
+        Arc::clone(&this.0.value)
+    }
+
+    /// Returns whether both guards refer to the same entry, not merely to equal values.
+    pub fn ptr_eq(this: &MapGuard<V>, other: &MapGuard<V>) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+
+    /// Returns how many guards refer to the entry, counting the one held by the
+    /// map while it is cached and by the [`entry_pool`](FixedSizeLruMapBuilder::entry_pool).
+    ///
+    /// A count staying high long after an eviction reveals guards held too long.
+    pub fn strong_count(this: &MapGuard<V>) -> usize {
+        Arc::strong_count(&this.0)
     }
 
     pub fn try_unwrap(this: MapGuard<V>) -> Result<V, MapGuard<V>> {
-        match Arc::try_unwrap(this.0) {
-            Ok(inner) => Ok(inner.1),
-            Err(arc) => Err(MapGuard(arc)),
+        let entry = match Arc::try_unwrap(this.0) {
+            Ok(entry) => entry,
+            Err(arc) => return Err(MapGuard(arc)),
+        };
+
+        // the value may still be shared through into_arc.
+        match Arc::try_unwrap(entry.value) {
+            Ok(value) => Ok(value),
+            Err(value) => Err(MapGuard(Arc::new(Entry { value, ..entry }))),
         }
     }
+
+    /// Returns the value, moved out when this is its last guard, cloned otherwise.
+    pub fn try_unwrap_or_clone(this: MapGuard<V>) -> V
+    where
+        V: Clone,
+    {
+        Self::try_unwrap(this).unwrap_or_else(|guard| V::clone(&guard))
+    }
+
+    /// Returns how many [`WeakMapGuard`] refer to the entry.
+    pub fn weak_count(this: &MapGuard<V>) -> usize {
+        Arc::weak_count(&this.0)
+    }
+}
+
+/// The metadata of an entry, see [`MapGuard::info`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EntryInfo {
+    /// When the value was inserted, `None` without timestamps.
+    pub created_at: Option<Instant>,
+    /// Number of reads of the value from the map.
+    pub hits: u64,
+    /// When the value was last read from the map, its insertion if never read.
+    /// `None` without timestamps.
+    pub last_accessed_at: Option<Instant>,
+}
+
+/// A weak handle to a cached value, see [`MapGuard::downgrade`].
+pub struct WeakMapGuard<V>(Weak<Entry<V>>);
+
+impl<V> WeakMapGuard<V> {
+    /// Returns a guard to the value, unless it has been dropped since.
+    pub fn upgrade(&self) -> Option<MapGuard<V>> {
+        self.0.upgrade().map(MapGuard)
+    }
+}
+
+impl<V> Clone for WeakMapGuard<V> {
+    fn clone(&self) -> Self {
+        WeakMapGuard(Weak::clone(&self.0))
+    }
+}
+
+impl<V> AsRef<V> for MapGuard<V> {
+    fn as_ref(&self) -> &V {
+        &self.0.value
+    }
+}
+
+impl<V> Borrow<V> for MapGuard<V> {
+    fn borrow(&self) -> &V {
+        &self.0.value
+    }
 }
 
 impl<V> Clone for MapGuard<V> {
@@ -141,12 +969,30 @@ impl<V> Clone for MapGuard<V> {
     }
 }
 
+impl<V> Debug for MapGuard<V>
+where
+    V: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.value.fmt(f)
+    }
+}
+
 impl<V> Deref for MapGuard<V> {
     type Target = V;
 
     #[inline]
     fn deref(&self) -> &Self::Target {
-        &(self.0).1
+        &self.0.value
+    }
+}
+
+impl<V> Display for MapGuard<V>
+where
+    V: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.value.fmt(f)
     }
 }
 
@@ -157,7 +1003,7 @@ where
     V: Hash,
 {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        (self.0).1.hash(state)
+        self.0.value.hash(state)
     }
 }
 
@@ -166,7 +1012,7 @@ where
     V: Ord,
 {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        (self.0).1.cmp(&(other.0).1)
+        self.0.value.cmp(&other.0.value)
     }
 }
 
@@ -175,17 +1021,419 @@ where
     V: PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        (self.0).1 == (other.0).1
+        self.0.value == other.0.value
+    }
+}
+
+impl<V> PartialEq<V> for MapGuard<V>
+where
+    V: PartialEq,
+{
+    fn eq(&self, other: &V) -> bool {
+        *self.0.value == *other
     }
 }
 
+impl<V> PartialOrd<V> for MapGuard<V>
+where
+    V: PartialOrd,
+{
+    fn partial_cmp(&self, other: &V) -> Option<std::cmp::Ordering> {
+        (*self.0.value).partial_cmp(other)
+    }
+}
+
+// The orphan rule forbids `impl<V> PartialEq<MapGuard<V>> for V`, so the
+// symmetric comparisons are provided for the common std value types only.
+macro_rules! symmetric_cmp {
+    ($($t:ty),*) => {
+        $(
+            impl PartialEq<MapGuard<$t>> for $t {
+                fn eq(&self, other: &MapGuard<$t>) -> bool {
+                    *self == *other.0.value
+                }
+            }
+
+            impl PartialOrd<MapGuard<$t>> for $t {
+                fn partial_cmp(&self, other: &MapGuard<$t>) -> Option<std::cmp::Ordering> {
+                    self.partial_cmp(&*other.0.value)
+                }
+            }
+        )*
+    };
+}
+
+symmetric_cmp!(
+    bool, char, f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, String
+);
+
 impl<V> PartialOrd for MapGuard<V>
 where
     V: PartialOrd,
 {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        (self.0).1.partial_cmp(&(other.0).1)
+        self.0.value.partial_cmp(&other.0.value)
+    }
+}
+
+#[test]
+fn test_guard_fmt() {
+    let map = FixedSizeLruMap::with_capacity(1);
+    let (guard, _) = map.insert(1, "a");
+    assert_eq!(
+        ("\"a\"", "a"),
+        (&*format!("{guard:?}"), &*guard.to_string())
+    );
+}
+
+#[test]
+fn test_ptr_eq() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    let (a, _) = map.insert(1, 'a');
+    let (b, _) = map.insert(2, 'a');
+
+    assert_eq!(a, b);
+    assert!(!MapGuard::ptr_eq(&a, &b));
+    assert!(MapGuard::ptr_eq(&a, &map.get(&1).unwrap()));
+}
+
+#[test]
+fn test_guard_as_ref() {
+    fn contains<B: Borrow<String>>(items: &[B], s: &str) -> bool {
+        items.iter().any(|i| i.borrow() == s)
+    }
+
+    fn len(s: impl AsRef<String>) -> usize {
+        s.as_ref().len()
+    }
+
+    let map = FixedSizeLruMap::with_capacity(1);
+    let (guard, _) = map.insert(1, "abc".to_owned());
+
+    assert!(contains(std::slice::from_ref(&guard), "abc"));
+    assert_eq!(3, len(guard));
+}
+
+#[test]
+fn test_entry_info() {
+    let map = FixedSizeLruMap::builder(1).timestamps(true).build();
+    let (guard, _) = map.insert(1, 'a');
+
+    let info = MapGuard::info(&guard);
+    assert!(info.created_at.is_some());
+    assert_eq!((0, info.created_at), (info.hits, info.last_accessed_at));
+
+    std::thread::sleep(Duration::from_millis(2));
+    map.get(&1);
+
+    let info = MapGuard::info(&guard);
+    assert_eq!(1, info.hits);
+    assert!(info.last_accessed_at >= info.created_at.map(|at| at + Duration::from_millis(2)));
+
+    // without timestamps only the hits are counted.
+    let map = FixedSizeLruMap::with_capacity(1);
+    map.insert(1, 'a');
+    let info = MapGuard::info(&map.get(&1).unwrap());
+    assert_eq!(
+        (1, None, None),
+        (info.hits, info.created_at, info.last_accessed_at)
+    );
+}
+
+#[test]
+fn test_try_unwrap_or_clone() {
+    let map = FixedSizeLruMap::with_capacity(1);
+    let (a, _) = map.insert(1, "a".to_owned());
+    assert_eq!("a", MapGuard::try_unwrap_or_clone(a));
+
+    let (_, evicted) = map.insert(2, "b".to_owned());
+    let evicted = evicted.unwrap();
+    let ptr = evicted.as_ptr();
+    let b = MapGuard::try_unwrap_or_clone(evicted);
+    assert_eq!(ptr, b.as_ptr());
+}
+
+#[test]
+fn test_guard_eq_value() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    let (a, _) = map.insert(1, "a".to_owned());
+    let numbers = FixedSizeLruMap::with_capacity(2);
+    let (n, _) = numbers.insert(1, 5u32);
+
+    assert_eq!(a, "a".to_owned());
+    assert_eq!("a".to_owned(), a);
+    assert_eq!(n, 5);
+    assert_eq!(5, n);
+    assert!(n < 6 && 4 < n);
+}
+
+#[test]
+fn test_borrowed_lookup() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    map.insert("a".to_owned(), 1);
+    map.insert("b".to_owned(), 2);
+
+    assert_eq!(1, *map.get("a").unwrap());
+    assert!(map.contains_key("a"));
+    assert!(map.view().get("c").is_none());
+}
+
+#[test]
+fn test_guard_counts() {
+    let map = FixedSizeLruMap::with_capacity(1);
+    let (a, _) = map.insert(1, 'a');
+    let _weak = MapGuard::downgrade(&a);
+    assert_eq!(
+        (2, 1),
+        (MapGuard::strong_count(&a), MapGuard::weak_count(&a))
+    );
+
+    map.insert(2, 'b');
+    assert_eq!(1, MapGuard::strong_count(&a));
+}
+
+#[test]
+fn test_entry_pool_reuse() {
+    let map = FixedSizeLruMap::builder(1).entry_pool(4).build();
+    map.insert(1, 'a');
+
+    let (_, evicted) = map.insert(2, 'b');
+    let evicted = evicted.unwrap();
+    let ptr = Arc::as_ptr(&evicted.0);
+
+    // still held outside, so the allocation cannot be reused.
+    let (c, _) = map.insert(3, 'c');
+    assert_ne!(ptr, Arc::as_ptr(&c.0));
+    drop(evicted);
+
+    let (d, _) = map.insert(4, 'd');
+    assert_eq!(ptr, Arc::as_ptr(&d.0));
+    assert_eq!('d', *d);
+}
+
+#[test]
+fn test_age_rebase() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    map.insert(1, 'a');
+    map.insert(2, 'b');
+    map.get(&1);
+
+    map.age.store(age::REBASE_THRESHOLD, Relaxed);
+
+    // 2 is still the least recently used after the rebase.
+    let (_, evicted) = map.insert(3, 'c');
+    assert_eq!('b', *evicted.unwrap());
+    assert!(map.age.load(Relaxed) < 10);
+
+    map.get(&1);
+    let (_, evicted) = map.insert(4, 'd');
+    assert_eq!('c', *evicted.unwrap());
+}
+
+#[test]
+fn test_evictions() {
+    let map = FixedSizeLruMap::with_capacity(1);
+    let mut evictions = map.evictions();
+
+    map.insert(1, 'a');
+    map.insert(1, 'b');
+    assert!(evictions.try_recv().is_none());
+
+    map.insert(2, 'c');
+    let (k, v) = future::block_on(evictions.recv()).unwrap();
+    assert_eq!((1, 'b'), (k, *v));
+
+    drop(map);
+    assert!(future::block_on(evictions.recv()).is_none());
+}
+
+#[test]
+fn test_removal_listener() {
+    let removed = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let r = Arc::clone(&removed);
+    let map = FixedSizeLruMap::builder(1)
+        .removal_listener(move |k, v: MapGuard<char>, cause| r.lock().push((k, *v, cause)))
+        .build();
+
+    map.insert(1, 'a');
+    map.insert(1, 'b');
+    map.insert(2, 'c');
+    map.remove(&2);
+
+    assert_eq!(
+        vec![
+            (1, 'a', RemovalCause::Replaced),
+            (1, 'b', RemovalCause::Capacity),
+            (2, 'c', RemovalCause::Explicit),
+        ],
+        *removed.lock()
+    );
+}
+
+#[test]
+fn test_subscribe() {
+    let map = FixedSizeLruMap::with_capacity(10);
+    let mut events = map.subscribe(2);
+
+    for i in 0..5 {
+        map.insert(i, i);
+    }
+
+    let observer = std::thread::spawn(move || {
+        let keys = [events.blocking_recv(), events.blocking_recv()]
+            .map(|e| matches!(e, Some(CacheEvent::Insert(k, _)) if k >= 3));
+        (keys, events.dropped())
+    });
+
+    assert_eq!(([true, true], 3), observer.join().unwrap());
+}
+
+#[test]
+fn test_listener_reentrancy() {
+    use std::sync::{OnceLock, Weak};
+
+    type Map = FixedSizeLruMap<i32, i32>;
+
+    let this = Arc::new(OnceLock::<Weak<Map>>::new());
+    let seen = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let (t, s) = (Arc::clone(&this), Arc::clone(&seen));
+
+    let map = Arc::new(
+        FixedSizeLruMap::builder(1)
+            .removal_listener(move |k, _, _| {
+                let map = t.get().and_then(Weak::upgrade).unwrap();
+                s.lock().push((k, map.contains_key(&k), map.len()));
+            })
+            .build(),
+    );
+
+    this.set(Arc::downgrade(&map)).unwrap();
+    map.insert(1, 1);
+    map.insert(2, 2);
+    map.remove(&2);
+
+    assert_eq!(vec![(1, false, 1), (2, false, 0)], *seen.lock());
+}
+
+#[test]
+fn test_hooks() {
+    let log = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let (l1, l2) = (Arc::clone(&log), Arc::clone(&log));
+    let map = FixedSizeLruMap::builder(2)
+        .on_insert(move |k, v| l1.lock().push(format!("insert {k} {}", **v)))
+        .on_update(move |k, old, new| l2.lock().push(format!("update {k} {} {}", **old, **new)))
+        .build();
+
+    map.insert(1, 'a');
+    map.insert(1, 'b');
+
+    assert_eq!(vec!["insert 1 a", "update 1 a b"], *log.lock());
+}
+
+#[test]
+fn test_events() {
+    let map = FixedSizeLruMap::with_capacity(1);
+    let mut events = map.events();
+
+    map.insert(1, 'a');
+    map.insert(1, 'b');
+    map.insert(2, 'c');
+    map.remove(&2);
+
+    let mut next = || events.try_recv().unwrap();
+    assert!(matches!(next(), CacheEvent::Insert(1, v) if *v == 'a'));
+    assert!(
+        matches!(next(), CacheEvent::Update { key: 1, old, new } if *old == 'a' && *new == 'b')
+    );
+    assert!(matches!(next(), CacheEvent::Insert(2, _)));
+    assert!(matches!(next(), CacheEvent::Evict(1, _)));
+    assert!(matches!(next(), CacheEvent::Remove(2, _)));
+}
+
+#[test]
+fn test_clear() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    let mut events = map.events();
+    map.insert(1, 'a');
+    map.insert(2, 'b');
+    map.depends_on(2, 1);
+
+    map.clear();
+    assert!(map.is_empty());
+
+    let removed = std::iter::from_fn(|| events.try_recv())
+        .filter(|e| matches!(e, CacheEvent::Remove(..)))
+        .count();
+    assert_eq!(2, removed);
+}
+
+#[test]
+fn test_remove_without_clone() {
+    // removing needs no `K: Clone`, the event is built from the removed key.
+    fn remove<K: Eq + Hash, V>(map: &FixedSizeLruMap<K, V>, key: &K) -> bool {
+        map.remove_if(key, |_| true).is_some()
     }
+
+    let map = FixedSizeLruMap::with_capacity(1);
+    let mut events = map.events();
+    map.insert(1, 'a');
+
+    assert!(remove(&map, &1));
+    assert!(matches!(
+        events.try_recv().unwrap(),
+        CacheEvent::Insert(1, _)
+    ));
+    assert!(matches!(
+        events.try_recv().unwrap(),
+        CacheEvent::Remove(1, _)
+    ));
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_stats() {
+    let map = FixedSizeLruMap::with_capacity(1);
+    map.get_or_init(1, || 'a');
+    map.get_or_init(1, || 'b');
+    map.insert(2, 'c');
+
+    let stats = map.stats();
+    assert_eq!(1, stats.evictions);
+    assert_eq!(1, stats.hits);
+    assert_eq!(2, stats.insertions);
+    assert_eq!(1, stats.misses);
+    assert_eq!(0.5, stats.hit_ratio());
+
+    map.insert(2, 'd');
+    map.remove(&2);
+    let delta = map.stats_delta_since(&stats);
+    assert_eq!((1, 1), (delta.removals, delta.replacements));
+
+    map.get(&2);
+    assert_eq!(1, map.stats_delta_since(&stats).misses);
+    assert_eq!(1, map.stats_reset().hits);
+    assert_eq!(CacheStats::default(), map.stats());
+}
+
+#[test]
+fn test_hits() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    map.insert(1, 'a');
+    map.insert(2, 'b');
+    map.get(&1);
+    map.get_or_init(1, || 'c');
+
+    let mut hits = map
+        .entries()
+        .into_iter()
+        .map(|(k, v)| (k, MapGuard::hits(&v)))
+        .collect::<Vec<_>>();
+    hits.sort();
+    assert_eq!(vec![(1, 2), (2, 0)], hits);
+
+    let hottest = map.hottest(1);
+    assert_eq!(1, hottest.len());
+    assert_eq!(1, hottest[0].0);
 }
 
 #[test]
@@ -0,0 +1,200 @@
+//! Read-through caching where the values are loaded by a [`CacheLoader`].
+use crate::{FixedSizeLruMap, MapGuard, RetryPolicy};
+use std::{
+    collections::hash_map::RandomState,
+    future::Future,
+    hash::{BuildHasher, Hash},
+};
+
+/// Loads the value of a key missing from a [`ReadThroughCache`].
+///
+/// Implemented for every `Fn(&K) -> V`.
+pub trait CacheLoader<K, V> {
+    fn load(&self, key: &K) -> V;
+}
+
+impl<K, V, F> CacheLoader<K, V> for F
+where
+    F: Fn(&K) -> V,
+{
+    fn load(&self, key: &K) -> V {
+        self(key)
+    }
+}
+
+/// Asynchronously loads the value of a key missing from a [`ReadThroughCache`].
+///
+/// Implemented for every `Fn(&K) -> impl Future<Output = V>`.
+pub trait AsyncCacheLoader<K, V> {
+    fn load_async(&self, key: &K) -> impl Future<Output = V>;
+}
+
+impl<K, V, F, Fut> AsyncCacheLoader<K, V> for F
+where
+    F: Fn(&K) -> Fut,
+    Fut: Future<Output = V>,
+{
+    fn load_async(&self, key: &K) -> impl Future<Output = V> {
+        self(key)
+    }
+}
+
+/// A cache loading the missing values itself, so the load-on-miss policy lives
+/// in one place instead of at every call site.
+///
+/// A loader returning a `Result` is called through [`try_get`](Self::try_get)
+/// and retried according to the [`RetryPolicy`] of the cache.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::ReadThroughCache;
+///
+/// let cache = ReadThroughCache::new(10, |k: &u32| k * 2);
+/// assert_eq!(4, *cache.get(&2));
+/// ```
+pub struct ReadThroughCache<K, V, L, S = RandomState> {
+    loader: L,
+    map: FixedSizeLruMap<K, V, S>,
+    retry: RetryPolicy,
+}
+
+impl<K, V, L> ReadThroughCache<K, V, L>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn new(capacity: usize, loader: L) -> Self {
+        Self::with_map(FixedSizeLruMap::with_capacity(capacity), loader)
+    }
+}
+
+impl<K, V, L, S> ReadThroughCache<K, V, L, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn with_map(map: FixedSizeLruMap<K, V, S>, loader: L) -> Self {
+        ReadThroughCache {
+            loader,
+            map,
+            retry: RetryPolicy::none(),
+        }
+    }
+
+    /// Gets the value of `key`, loading it on a miss.
+    pub fn get(&self, key: &K) -> MapGuard<V>
+    where
+        L: CacheLoader<K, V>,
+    {
+        match self.map.get(key) {
+            Some(value) => value,
+            None => {
+                self.map
+                    .insert(key.clone(), self.map.load(key, || self.loader.load(key)))
+                    .0
+            }
+        }
+    }
+
+    /// Gets the value of `key`, loading it asynchronously on a miss.
+    ///
+    /// Concurrent misses on the same key share a single load.
+    pub async fn get_async(&self, key: &K) -> MapGuard<V>
+    where
+        L: AsyncCacheLoader<K, V>,
+    {
+        self.map
+            .get_or_init_async(key.clone(), || self.loader.load_async(key))
+            .await
+    }
+
+    /// Retries the failed loads of [`try_get`](Self::try_get) and
+    /// [`try_get_async`](Self::try_get_async) according to `policy`.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Gets the value of `key`, loading it on a miss with a fallible loader.
+    ///
+    /// Nothing is cached when every attempt fails, the last error is returned.
+    pub fn try_get<E>(&self, key: &K) -> Result<MapGuard<V>, E>
+    where
+        L: CacheLoader<K, Result<V, E>>,
+    {
+        if let Some(value) = self.map.get(key) {
+            return Ok(value);
+        }
+
+        let value = self
+            .map
+            .load(key, || self.retry.run(|| self.loader.load(key)))?;
+        Ok(self.map.insert(key.clone(), value).0)
+    }
+
+    /// Gets the value of `key`, loading it asynchronously on a miss with a fallible loader.
+    ///
+    /// Concurrent misses on the same key share a single load and its retries.
+    pub async fn try_get_async<E>(&self, key: &K) -> Result<MapGuard<V>, E>
+    where
+        L: AsyncCacheLoader<K, Result<V, E>>,
+    {
+        self.map
+            .get_or_try_init_async(key.clone(), || {
+                self.retry.run_async(|| self.loader.load_async(key))
+            })
+            .await
+    }
+
+    /// Removes `key` so it is loaded again on the next access.
+    pub fn invalidate(&self, key: &K) -> Option<MapGuard<V>> {
+        self.map.remove(key)
+    }
+
+    pub fn loader(&self) -> &L {
+        &self.loader
+    }
+
+    /// Returns the underlying map, which can be used to insert values directly.
+    pub fn map(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.map
+    }
+}
+
+#[test]
+fn test_read_through_async() {
+    use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+    let loads = AtomicUsize::new(0);
+    let cache = ReadThroughCache::new(10, |k: &u32| {
+        loads.fetch_add(1, Relaxed);
+        let k = *k;
+        async move { k + 1 }
+    });
+
+    crate::future::block_on(async {
+        assert_eq!(2, *cache.get_async(&1).await);
+        assert_eq!(2, *cache.get_async(&1).await);
+    });
+
+    assert_eq!(1, loads.into_inner());
+}
+
+#[test]
+fn test_read_through_retry() {
+    use std::{cell::Cell, time::Duration};
+
+    let failures = Cell::new(2);
+    let cache = ReadThroughCache::new(10, |k: &u32| {
+        if failures.get() > 0 {
+            failures.set(failures.get() - 1);
+            return Err("unavailable");
+        }
+
+        Ok(k + 1)
+    })
+    .with_retry(RetryPolicy::attempts(2).backoff(Duration::from_millis(1)));
+
+    assert_eq!(Err("unavailable"), cache.try_get(&1).map(|v| *v));
+    assert!(!cache.map().contains_key(&1));
+    assert_eq!(Ok(2), cache.try_get(&1).map(|v| *v));
+}
@@ -0,0 +1,119 @@
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(feature = "lock-stats")]
+use std::{
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+    time::{Duration, Instant},
+};
+
+/// The lock protecting the map internals.
+///
+/// With the `lock-stats` feature, every acquisition is counted and contended
+/// acquisitions are timed so the cost of the lock itself can be observed.
+pub(crate) struct Lock<T> {
+    inner: RwLock<T>,
+    #[cfg(feature = "lock-stats")]
+    counters: LockCounters,
+}
+
+impl<T> Lock<T> {
+    pub fn new(value: T) -> Self {
+        Lock {
+            inner: RwLock::new(value),
+            #[cfg(feature = "lock-stats")]
+            counters: LockCounters::default(),
+        }
+    }
+
+    #[cfg(not(feature = "lock-stats"))]
+    #[inline]
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.inner.read()
+    }
+
+    #[cfg(feature = "lock-stats")]
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.counters.read_acquisitions.fetch_add(1, Relaxed);
+
+        if let Some(guard) = self.inner.try_read() {
+            return guard;
+        }
+
+        self.counters.failed_try_locks.fetch_add(1, Relaxed);
+        self.inner.read()
+    }
+
+    #[cfg(not(feature = "lock-stats"))]
+    #[inline]
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.inner.write()
+    }
+
+    #[cfg(feature = "lock-stats")]
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.counters.write_acquisitions.fetch_add(1, Relaxed);
+
+        if let Some(guard) = self.inner.try_write() {
+            return guard;
+        }
+
+        self.counters.failed_try_locks.fetch_add(1, Relaxed);
+
+        let start = Instant::now();
+        let guard = self.inner.write();
+        let nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+
+        self.counters.write_wait_nanos.fetch_add(nanos, Relaxed);
+        guard
+    }
+
+    #[cfg(feature = "lock-stats")]
+    pub fn stats(&self) -> LockStats {
+        LockStats {
+            read_acquisitions: self.counters.read_acquisitions.load(Relaxed),
+            write_acquisitions: self.counters.write_acquisitions.load(Relaxed),
+            failed_try_locks: self.counters.failed_try_locks.load(Relaxed),
+            write_wait: Duration::from_nanos(self.counters.write_wait_nanos.load(Relaxed)),
+        }
+    }
+}
+
+#[cfg(feature = "lock-stats")]
+#[derive(Default)]
+struct LockCounters {
+    read_acquisitions: AtomicU64,
+    write_acquisitions: AtomicU64,
+    failed_try_locks: AtomicU64,
+    write_wait_nanos: AtomicU64,
+}
+
+/// Counters describing how the internal lock has been used.
+#[cfg(feature = "lock-stats")]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LockStats {
+    /// Number of times the read lock was acquired.
+    pub read_acquisitions: u64,
+    /// Number of times the write lock was acquired.
+    pub write_acquisitions: u64,
+    /// Number of acquisitions that could not be satisfied immediately and had to wait.
+    pub failed_try_locks: u64,
+    /// Total time spent waiting on a contended write lock.
+    pub write_wait: Duration,
+}
+
+#[cfg(feature = "lock-stats")]
+#[test]
+fn test_lock_stats() {
+    let lock = Lock::new(0);
+
+    *lock.write() += 1;
+
+    {
+        let _a = lock.read();
+        let _b = lock.read();
+    }
+
+    let stats = lock.stats();
+    assert_eq!(2, stats.read_acquisitions);
+    assert_eq!(1, stats.write_acquisitions);
+    assert_eq!(0, stats.failed_try_locks);
+}
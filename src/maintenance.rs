@@ -0,0 +1,114 @@
+//! Background housekeeping of a map.
+use crate::{age, FixedSizeLruMap};
+use std::{
+    hash::{BuildHasher, Hash},
+    sync::atomic::Ordering::Relaxed,
+};
+#[cfg(feature = "std")]
+use std::{
+    sync::{
+        mpsc::{channel, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{spawn, JoinHandle},
+    time::Duration,
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Rebases the ages close to overflowing, and drops the dependencies and the
+    /// tags of the entries evicted since.
+    ///
+    /// Nothing else is done: the pending notifications are still sent by the
+    /// writes, and the statistics are left untouched.
+    pub fn run_maintenance(&self) {
+        if self.age.load(Relaxed) >= age::REBASE_THRESHOLD {
+            let map = self.map.write();
+            age::rebase(&self.age, map.values().map(|v| &v.0.age));
+            self.check_invariants(&map);
+        }
+
+        self.prune_dependencies();
+        self.prune_tags();
+    }
+
+    /// Calls [`run_maintenance`](Self::run_maintenance) every `interval` on a
+    /// background thread until the returned handle is dropped.
+    ///
+    /// The thread does not keep the map alive, it stops by itself once the map is dropped.
+    #[cfg(feature = "std")]
+    pub fn spawn_maintenance(self: &Arc<Self>, interval: Duration) -> MaintenanceHandle
+    where
+        K: Send + Sync + 'static,
+        V: Send + Sync + 'static,
+        S: Send + Sync + 'static,
+    {
+        spawn_periodic(self, interval, Self::run_maintenance)
+    }
+}
+
+/// Calls `f` on `target` every `interval` until the handle or the target is dropped.
+#[cfg(feature = "std")]
+pub(crate) fn spawn_periodic<T, F>(
+    target: &Arc<T>,
+    interval: Duration,
+    mut f: F,
+) -> MaintenanceHandle
+where
+    F: FnMut(&T) + Send + 'static,
+    T: Send + Sync + 'static,
+{
+    let target = Arc::downgrade(target);
+    let (stop, stopped) = channel::<()>();
+
+    let thread = spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            match target.upgrade() {
+                Some(target) => f(&target),
+                None => break,
+            }
+        }
+    });
+
+    MaintenanceHandle {
+        stop: Some(stop),
+        thread: Some(thread),
+    }
+}
+
+/// Stops a background maintenance thread when dropped.
+#[cfg(feature = "std")]
+pub struct MaintenanceHandle {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[cfg(feature = "std")]
+impl Drop for MaintenanceHandle {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_spawn_maintenance() {
+    use std::thread::sleep;
+
+    let map = Arc::new(FixedSizeLruMap::with_capacity(2));
+    map.insert(1, 'a');
+    map.age.store(age::REBASE_THRESHOLD, Relaxed);
+
+    let handle = map.spawn_maintenance(Duration::from_millis(1));
+    sleep(Duration::from_millis(50));
+    drop(handle);
+
+    assert_eq!(1, map.age.load(Relaxed));
+}
@@ -0,0 +1,145 @@
+//! Guards scoped to a part of a cached value.
+use crate::MapGuard;
+use std::{
+    any::Any,
+    fmt::{self, Debug, Display, Formatter},
+    ops::Deref,
+    sync::Arc,
+};
+
+/// A guard to a part of a cached value, returned by [`MapGuard::map`].
+///
+/// It keeps the whole entry alive but only gives access to the projected part.
+pub struct MappedMapGuard<T: ?Sized> {
+    _owner: Arc<dyn Any + Send + Sync>,
+    ptr: *const T,
+}
+
+// the pointee is only ever shared, and the owner it points into is Send + Sync.
+unsafe impl<T: ?Sized + Sync> Send for MappedMapGuard<T> {}
+unsafe impl<T: ?Sized + Sync> Sync for MappedMapGuard<T> {}
+
+impl<V> MapGuard<V>
+where
+    V: Send + Sync + 'static,
+{
+    /// Returns a guard to the part of the value selected by `f`.
+    ///
+    /// This is an associated function so it cannot shadow a method of `V`.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::{FixedSizeLruMap, MapGuard};
+    ///
+    /// struct User {
+    ///     name: String,
+    ///     password_hash: String,
+    /// }
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// let (user, _) = map.insert(1, User { name: "ann".into(), password_hash: "...".into() });
+    ///
+    /// let name = MapGuard::map(user, |u| &u.name);
+    /// assert_eq!("ann", *name);
+    /// ```
+    pub fn map<T, F>(this: Self, f: F) -> MappedMapGuard<T>
+    where
+        F: FnOnce(&V) -> &T,
+        T: ?Sized,
+    {
+        let ptr = f(&this) as *const T;
+        MappedMapGuard {
+            _owner: this.0,
+            ptr,
+        }
+    }
+
+    /// Returns a guard to the part of the value selected by `f`, or the guard
+    /// itself when there is none, e.g. when the value is not the expected variant.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::{FixedSizeLruMap, MapGuard};
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// let (ok, _) = map.insert(1, Ok::<_, String>(42));
+    /// let (err, _) = map.insert(2, Err("not found".to_owned()));
+    ///
+    /// assert_eq!(42, *MapGuard::try_map(ok, |v| v.as_ref().ok()).unwrap());
+    /// assert!(MapGuard::try_map(err, |v| v.as_ref().ok()).is_err());
+    /// ```
+    pub fn try_map<T, F>(this: Self, f: F) -> Result<MappedMapGuard<T>, Self>
+    where
+        F: FnOnce(&V) -> Option<&T>,
+        T: ?Sized,
+    {
+        let Some(ptr) = f(&this).map(|part| part as *const T) else {
+            return Err(this);
+        };
+
+        Ok(MappedMapGuard {
+            _owner: this.0,
+            ptr,
+        })
+    }
+}
+
+impl<T: ?Sized> MappedMapGuard<T> {
+    /// Narrows the guard further to the part selected by `f`.
+    pub fn map<U, F>(this: Self, f: F) -> MappedMapGuard<U>
+    where
+        F: FnOnce(&T) -> &U,
+        U: ?Sized,
+    {
+        let ptr = f(&this) as *const U;
+
+        MappedMapGuard {
+            _owner: this._owner,
+            ptr,
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for MappedMapGuard<T> {
+    fn clone(&self) -> Self {
+        MappedMapGuard {
+            _owner: Arc::clone(&self._owner),
+            ptr: self.ptr,
+        }
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for MappedMapGuard<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized> Deref for MappedMapGuard<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        // the pointee lives in the entry kept alive by the owner, which is never mutated.
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T: ?Sized + Display> Display for MappedMapGuard<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+#[test]
+fn test_mapped_guard() {
+    let map = crate::FixedSizeLruMap::with_capacity(1);
+    let (guard, _) = map.insert(1, ("a".to_owned(), vec![1, 2, 3]));
+
+    let items = MapGuard::map(guard, |v| &v.1[..]);
+    let last = MappedMapGuard::map(items.clone(), |v| &v[2]);
+
+    // the entry outlives its eviction while a mapped guard holds it.
+    map.insert(2, Default::default());
+    assert_eq!((&[1, 2, 3][..], 3), (&*items, *last));
+}
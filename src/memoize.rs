@@ -0,0 +1,216 @@
+//! Memoization of the items flowing through an iterator, or of the calls to a function.
+use crate::{FixedSizeLruMap, MapGuard};
+use std::hash::{BuildHasher, Hash};
+
+/// Adds [`memoize`](MemoizeExt::memoize) to every iterator.
+pub trait MemoizeExt: Iterator + Sized {
+    /// Maps the items with `f`, reusing the result cached in `map` for the items
+    /// having the same `key`, so repeated items are only computed once while
+    /// they stay in the map.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::{FixedSizeLruMap, MemoizeExt};
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// let mut calls = 0;
+    ///
+    /// let lengths = ["a", "bb", "a", "bb"]
+    ///     .into_iter()
+    ///     .memoize(&map, |s| s.to_string(), |s| {
+    ///         calls += 1;
+    ///         s.len()
+    ///     })
+    ///     .map(|len| *len)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec![1, 2, 1, 2], lengths);
+    /// assert_eq!(2, calls);
+    /// ```
+    fn memoize<K, V, S, KF, F>(
+        self,
+        map: &FixedSizeLruMap<K, V, S>,
+        key: KF,
+        f: F,
+    ) -> Memoize<'_, Self, K, V, S, KF, F>
+    where
+        K: Clone + Eq + Hash,
+        S: BuildHasher,
+        KF: FnMut(&Self::Item) -> K,
+        F: FnMut(Self::Item) -> V,
+    {
+        Memoize {
+            f,
+            iter: self,
+            key,
+            map,
+        }
+    }
+}
+
+impl<I: Iterator> MemoizeExt for I {}
+
+/// An iterator returned by [`memoize`](MemoizeExt::memoize).
+pub struct Memoize<'a, I, K, V, S, KF, F> {
+    f: F,
+    iter: I,
+    key: KF,
+    map: &'a FixedSizeLruMap<K, V, S>,
+}
+
+impl<I, K, V, S, KF, F> Iterator for Memoize<'_, I, K, V, S, KF, F>
+where
+    I: Iterator,
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+    KF: FnMut(&I::Item) -> K,
+    F: FnMut(I::Item) -> V,
+{
+    type Item = MapGuard<V>;
+
+    fn next(&mut self) -> Option<MapGuard<V>> {
+        let item = self.iter.next()?;
+        let key = (self.key)(&item);
+        let f = &mut self.f;
+        Some(self.map.get_or_init(key, || f(item)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Wraps a function with a [`FixedSizeLruMap`] keeping its most recent results,
+/// so the calls with the same arguments are only computed once while they stay
+/// in the map.
+///
+/// The arguments must be `Clone + Eq + Hash` and the result `Clone`. An optional
+/// `ttl` recomputes the results older than the given [`Duration`](std::time::Duration).
+/// Async functions are supported, concurrent calls with the same arguments may
+/// both compute the result.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::lru_memoize;
+/// use std::time::Duration;
+///
+/// lru_memoize! {
+///     capacity = 100, ttl = Duration::from_secs(60);
+///     fn fib(n: u64) -> u64 {
+///         if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+///     }
+/// }
+///
+/// assert_eq!(12586269025, fib(50));
+/// ```
+#[cfg(feature = "macros")]
+#[macro_export]
+macro_rules! lru_memoize {
+    (
+        capacity = $capacity:expr $(, ttl = $ttl:expr)?;
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> $ret:ty $body:block
+    ) => {
+        $(#[$attr])*
+        $vis fn $name($($arg: $ty),*) -> $ret {
+            fn inner($($arg: $ty),*) -> $ret $body
+
+            $crate::lru_memoize!(@lookup map, key, $capacity, $($ttl)?, ($($arg: $ty),*) -> $ret);
+            let value = inner($($arg),*);
+            map.insert(key, (::std::time::Instant::now(), ::std::clone::Clone::clone(&value)));
+            value
+        }
+    };
+    (
+        capacity = $capacity:expr $(, ttl = $ttl:expr)?;
+        $(#[$attr:meta])*
+        $vis:vis async fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> $ret:ty $body:block
+    ) => {
+        $(#[$attr])*
+        $vis async fn $name($($arg: $ty),*) -> $ret {
+            async fn inner($($arg: $ty),*) -> $ret $body
+
+            $crate::lru_memoize!(@lookup map, key, $capacity, $($ttl)?, ($($arg: $ty),*) -> $ret);
+            let value = inner($($arg),*).await;
+            map.insert(key, (::std::time::Instant::now(), ::std::clone::Clone::clone(&value)));
+            value
+        }
+    };
+    (@lookup $map:ident, $key:ident, $capacity:expr, $($ttl:expr)?, ($($arg:ident: $ty:ty),*) -> $ret:ty) => {
+        #[allow(clippy::type_complexity)]
+        static MAP: ::std::sync::OnceLock<
+            $crate::FixedSizeLruMap<($($ty,)*), (::std::time::Instant, $ret)>,
+        > = ::std::sync::OnceLock::new();
+
+        let $map = MAP.get_or_init(|| $crate::FixedSizeLruMap::with_capacity($capacity));
+        let $key = ($(::std::clone::Clone::clone(&$arg),)*);
+        let ttl: ::std::option::Option<::std::time::Duration> = None $(.or(Some($ttl)))?;
+
+        if let Some(hit) = $map.get(&$key) {
+            let (at, value) = &*hit;
+
+            if ttl.map_or(true, |ttl| at.elapsed() < ttl) {
+                return ::std::clone::Clone::clone(value);
+            }
+        }
+    };
+}
+
+#[test]
+fn test_memoize_evicted() {
+    let map = FixedSizeLruMap::with_capacity(1);
+    let mut calls = 0;
+
+    let squares = [2, 3, 2, 2]
+        .into_iter()
+        .memoize(
+            &map,
+            |&n| n,
+            |n| {
+                calls += 1;
+                n * n
+            },
+        )
+        .map(|v| *v)
+        .collect::<Vec<_>>();
+
+    assert_eq!(vec![4, 9, 4, 4], squares);
+
+    // 2 was evicted by 3 so it had to be computed again.
+    assert_eq!(3, calls);
+}
+
+#[cfg(feature = "macros")]
+#[test]
+fn test_lru_memoize() {
+    use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    lru_memoize! {
+        capacity = 2;
+        fn concat(a: String, b: u8) -> String {
+            CALLS.fetch_add(1, Relaxed);
+            format!("{a}{b}")
+        }
+    }
+
+    lru_memoize! {
+        capacity = 2, ttl = std::time::Duration::ZERO;
+        async fn double(n: u32) -> u32 {
+            CALLS.fetch_add(1, Relaxed);
+            n * 2
+        }
+    }
+
+    assert_eq!("a1", concat("a".into(), 1));
+    assert_eq!("a1", concat("a".into(), 1));
+    assert_eq!(1, CALLS.load(Relaxed));
+
+    // every result has already expired.
+    crate::future::block_on(async {
+        assert_eq!(4, double(2).await);
+        assert_eq!(4, double(2).await);
+    });
+    assert_eq!(3, CALLS.load(Relaxed));
+}
@@ -0,0 +1,150 @@
+//! Export of the map counters to a telemetry stack.
+use crate::FixedSizeLruMap;
+use std::{
+    fmt::Write,
+    hash::{BuildHasher, Hash},
+};
+
+/// Whether a [`Metric`] only goes up or can go both ways.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+/// A named value describing a map, ready to be handed to a metrics facade.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metric {
+    pub help: &'static str,
+    pub kind: MetricKind,
+    pub name: String,
+    pub value: f64,
+}
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Returns the metrics of the map, their names starting with `prefix`.
+    ///
+    /// The counters of the `stats` feature are only included when it is enabled.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// map.insert(1, 'a');
+    ///
+    /// let len = map.metrics("users_cache").into_iter().find(|m| m.name == "users_cache_len");
+    /// assert_eq!(Some(1.0), len.map(|m| m.value));
+    /// ```
+    pub fn metrics(&self, prefix: &str) -> Vec<Metric> {
+        let gauge = |name: &str, help, value| Metric {
+            help,
+            kind: MetricKind::Gauge,
+            name: format!("{prefix}_{name}"),
+            value,
+        };
+
+        #[allow(unused_mut)]
+        let mut metrics = vec![
+            gauge("len", "Number of entries in the cache.", self.len() as f64),
+            gauge(
+                "capacity",
+                "Maximum number of entries in the cache.",
+                self.capacity as f64,
+            ),
+        ];
+
+        #[cfg(feature = "stats")]
+        {
+            let counter = |name: &str, help, value: u64| Metric {
+                help,
+                kind: MetricKind::Counter,
+                name: format!("{prefix}_{name}"),
+                value: value as f64,
+            };
+
+            let stats = self.stats();
+
+            metrics.extend([
+                counter(
+                    "hits_total",
+                    "Number of lookups that found a value.",
+                    stats.hits,
+                ),
+                counter(
+                    "misses_total",
+                    "Number of lookups that found nothing.",
+                    stats.misses,
+                ),
+                counter(
+                    "insertions_total",
+                    "Number of inserted values.",
+                    stats.insertions,
+                ),
+                counter(
+                    "evictions_total",
+                    "Number of entries evicted to make room.",
+                    stats.evictions,
+                ),
+                counter(
+                    "removals_total",
+                    "Number of entries explicitly removed.",
+                    stats.removals,
+                ),
+                counter(
+                    "replacements_total",
+                    "Number of values replaced by a new one.",
+                    stats.replacements,
+                ),
+                gauge(
+                    "hit_ratio",
+                    "Ratio of the recent lookups that found a value.",
+                    self.windowed_hit_ratio(),
+                ),
+            ]);
+        }
+
+        metrics
+    }
+
+    /// Renders the [`metrics`](Self::metrics) in the Prometheus text exposition
+    /// format, to be served as is by a scrape endpoint.
+    pub fn prometheus(&self, prefix: &str) -> String {
+        render_prometheus(&self.metrics(prefix))
+    }
+}
+
+/// Renders `metrics` in the Prometheus text exposition format.
+///
+/// The metrics of several caches can be concatenated and rendered at once, as
+/// long as their prefixes differ.
+pub fn render_prometheus(metrics: &[Metric]) -> String {
+    let mut out = String::new();
+
+    for m in metrics {
+        let kind = match m.kind {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        };
+
+        let _ = writeln!(out, "# HELP {} {}", m.name, m.help);
+        let _ = writeln!(out, "# TYPE {} {kind}", m.name);
+        let _ = writeln!(out, "{} {}", m.name, m.value);
+    }
+
+    out
+}
+
+#[test]
+fn test_prometheus() {
+    let map = FixedSizeLruMap::with_capacity(10);
+    map.insert(1, 'a');
+
+    let text = map.prometheus("cache");
+    assert!(text.contains("# TYPE cache_len gauge\ncache_len 1\n"));
+    assert!(text.contains("cache_capacity 10\n"));
+}
@@ -0,0 +1,121 @@
+//! An adapter mirroring the method names of moka's `sync::Cache`, to move code
+//! written against that API onto a [`FixedSizeLruMap`] with few edits.
+//!
+//! Like moka, the values are returned by clone, so they are usually cheap to
+//! clone types such as `Arc`s, and the cache itself is cloned to be shared.
+//!
+//! # Example
+//! ```
+//! use fixed_size_lru_map::moka::Cache;
+//!
+//! let cache = Cache::new(100);
+//! cache.insert("a", 1);
+//! assert_eq!(Some(1), cache.get(&"a"));
+//! assert_eq!(2, cache.get_with("b", || 2));
+//!
+//! cache.invalidate(&"a");
+//! assert_eq!(None, cache.get(&"a"));
+//! ```
+use crate::FixedSizeLruMap;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    sync::Arc,
+};
+
+/// A cache shared by cloning, with moka's method names.
+pub struct Cache<K, V, S = RandomState> {
+    map: Arc<FixedSizeLruMap<K, V, S>>,
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+{
+    /// Creates a cache holding up to `max_capacity` entries.
+    pub fn new(max_capacity: u64) -> Self {
+        Self::from_map(FixedSizeLruMap::with_capacity(max_capacity as usize))
+    }
+}
+
+impl<K, V, S> Cache<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+    S: BuildHasher,
+{
+    /// Wraps a map configured through its builder.
+    pub fn from_map(map: FixedSizeLruMap<K, V, S>) -> Self {
+        Cache { map: Arc::new(map) }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    pub fn entry_count(&self) -> u64 {
+        self.map.len() as u64
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.map.get(key).map(|v| V::clone(&v))
+    }
+
+    /// Returns the value of `key`, computing it with `init` on a miss.
+    ///
+    /// Unlike moka, concurrent misses on the same key each call their `init`.
+    pub fn get_with<F>(&self, key: K, init: F) -> V
+    where
+        F: FnOnce() -> V,
+    {
+        V::clone(&self.map.get_or_init(key, init))
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.map.insert(key, value);
+    }
+
+    pub fn invalidate(&self, key: &K) {
+        self.map.remove(key);
+    }
+
+    /// Removes every entry.
+    pub fn invalidate_all(&self) {
+        self.map.clear();
+    }
+
+    /// Returns the underlying map, for the features moka does not have.
+    pub fn map(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.map
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.map.remove(key).map(|v| V::clone(&v))
+    }
+}
+
+impl<K, V, S> Clone for Cache<K, V, S> {
+    fn clone(&self) -> Self {
+        Cache {
+            map: Arc::clone(&self.map),
+        }
+    }
+}
+
+#[test]
+fn test_moka_cache() {
+    let cache = Cache::new(2);
+    let shared = cache.clone();
+
+    cache.insert(1, "a");
+    cache.insert(2, "b");
+    assert_eq!(Some("a"), shared.get(&1));
+    assert_eq!("c", shared.get_with(3, || "c"));
+    assert!(!cache.contains_key(&2));
+    assert_eq!(2, cache.entry_count());
+
+    assert_eq!(Some("c"), cache.remove(&3));
+    cache.invalidate_all();
+    assert_eq!(0, shared.entry_count());
+}
@@ -0,0 +1,209 @@
+//! A fixed size lru map holding several values per key.
+use crate::{age, lock::Lock, MapGuard};
+use std::{
+    borrow::Borrow,
+    collections::{
+        hash_map::{HashMap, RandomState},
+        VecDeque,
+    },
+    hash::{BuildHasher, Hash},
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+};
+
+/// A lru map of keys to lists of values, bounded by its total number of values.
+///
+/// When full, the oldest value of the least recently used key is evicted, so
+/// the keys that stay in use keep their most recent values.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::LruMultiMap;
+///
+/// let messages = LruMultiMap::with_capacity(3);
+/// messages.insert_one("ann", "hi");
+/// messages.insert_one("bob", "hello");
+/// messages.insert_one("ann", "how are you?");
+///
+/// // "bob" is the least recently used conversation.
+/// let (key, evicted) = messages.insert_one("ann", "still there?").unwrap();
+/// assert_eq!(("bob", "hello"), (key, *evicted));
+///
+/// let ann = messages.get_all(&"ann");
+/// assert_eq!(vec!["hi", "how are you?", "still there?"], ann.iter().map(|m| **m).collect::<Vec<_>>());
+/// ```
+pub struct LruMultiMap<K, V, S = RandomState> {
+    age: AtomicU64,
+    capacity: usize,
+    inner: Lock<Inner<K, V, S>>,
+}
+
+struct Inner<K, V, S> {
+    keys: HashMap<K, Values<V>, S>,
+    len: usize,
+}
+
+struct Values<V> {
+    age: AtomicU64,
+    /// The values of the key, the oldest first.
+    values: VecDeque<MapGuard<V>>,
+}
+
+impl<K, V> LruMultiMap<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K, V, S> LruMultiMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        LruMultiMap {
+            age: AtomicU64::new(0),
+            capacity,
+            inner: Lock::new(Inner {
+                keys: HashMap::with_hasher(hash_builder),
+                len: 0,
+            }),
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.inner.read().keys.contains_key(key)
+    }
+
+    /// Returns the values of `key`, the oldest first, marking the key as recently used.
+    pub fn get_all<Q>(&self, key: &Q) -> Vec<MapGuard<V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let inner = self.inner.read();
+
+        let Some(values) = inner.keys.get(key) else {
+            return Vec::new();
+        };
+
+        values.age.store(self.age.fetch_add(1, Relaxed), Relaxed);
+        values.values.iter().cloned().collect()
+    }
+
+    /// Appends a value to the ones of `key`, returning the value evicted to make room.
+    pub fn insert_one(&self, key: K, value: V) -> Option<(K, MapGuard<V>)> {
+        let mut inner = self.inner.write();
+        let mut age = self.age.fetch_add(1, Relaxed);
+
+        if age >= age::REBASE_THRESHOLD {
+            age::rebase(&self.age, inner.keys.values().map(|v| &v.age));
+            age = self.age.fetch_add(1, Relaxed);
+        }
+
+        let guard = MapGuard::new(age, value);
+
+        if self.capacity == 0 {
+            return Some((key, guard));
+        }
+
+        let evicted = if inner.len >= self.capacity {
+            inner.evict()
+        } else {
+            None
+        };
+
+        let values = inner.keys.entry(key).or_insert_with(|| Values {
+            age: AtomicU64::new(age),
+            values: VecDeque::new(),
+        });
+
+        *values.age.get_mut() = age;
+        values.values.push_back(guard);
+        inner.len += 1;
+        evicted
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().len == 0
+    }
+
+    /// Returns the number of keys.
+    pub fn key_count(&self) -> usize {
+        self.inner.read().keys.len()
+    }
+
+    /// Returns the number of values, across all the keys.
+    pub fn len(&self) -> usize {
+        self.inner.read().len
+    }
+
+    /// Removes `key` with all its values, the oldest first.
+    pub fn remove_all<Q>(&self, key: &Q) -> Vec<MapGuard<V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let mut inner = self.inner.write();
+
+        let Some(values) = inner.keys.remove(key) else {
+            return Vec::new();
+        };
+
+        inner.len -= values.values.len();
+        values.values.into()
+    }
+}
+
+impl<K, V, S> Inner<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Removes the oldest value of the least recently used key.
+    fn evict(&mut self) -> Option<(K, MapGuard<V>)> {
+        let key = self
+            .keys
+            .iter()
+            .min_by_key(|(_, v)| v.age.load(Relaxed))
+            .map(|(k, _)| k.clone())?;
+
+        let values = self.keys.get_mut(&key)?;
+        let value = values.values.pop_front()?;
+
+        if values.values.is_empty() {
+            self.keys.remove(&key);
+        }
+
+        self.len -= 1;
+        Some((key, value))
+    }
+}
+
+#[test]
+fn test_lru_multimap() {
+    let map = LruMultiMap::with_capacity(3);
+    assert!(map.insert_one(1, "a").is_none());
+    assert!(map.insert_one(2, "b").is_none());
+    assert!(map.insert_one(1, "c").is_none());
+    assert_eq!(2, map.key_count());
+
+    // reading 2 makes 1 the least recently used key, its oldest value goes first.
+    assert_eq!(1, map.get_all(&2).len());
+    assert_eq!(Some((1, "a")), map.insert_one(3, "d").map(|(k, v)| (k, *v)));
+    assert_eq!(Some((1, "c")), map.insert_one(3, "e").map(|(k, v)| (k, *v)));
+    assert!(!map.contains_key(&1));
+
+    let removed = map.remove_all(&3);
+    assert_eq!(
+        vec!["d", "e"],
+        removed.iter().map(|v| **v).collect::<Vec<_>>()
+    );
+    assert_eq!(1, map.len());
+}
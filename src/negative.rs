@@ -0,0 +1,160 @@
+//! Negative caching of the keys known to have no value.
+use crate::{FixedSizeLruMap, MapGuard};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash},
+    sync::atomic::{AtomicBool, Ordering::Relaxed},
+    time::{Duration, Instant},
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Gets the value of `key` or tries to initialize it with `f`, remembering
+    /// when `f` found nothing.
+    ///
+    /// A missing key is not looked up again until its tombstone expires, see
+    /// [`negative_ttl`](crate::FixedSizeLruMapBuilder::negative_ttl), or a value
+    /// is inserted for it. The tombstones are bounded by the capacity of the map.
+    pub fn get_or_maybe_init<F>(&self, key: K, f: F) -> Option<MapGuard<V>>
+    where
+        F: FnOnce() -> Option<V>,
+    {
+        if let Some(value) = self.get(&key) {
+            return Some(value);
+        }
+
+        if self.negatives.contains(&key) {
+            return None;
+        }
+
+        match self.load(&key, f) {
+            Some(value) => Some(self.insert(key, value).0),
+            None => {
+                self.negatives.insert(key, self.capacity);
+                None
+            }
+        }
+    }
+
+    /// Returns true if `key` is currently cached as having no value.
+    pub fn is_negative(&self, key: &K) -> bool {
+        self.negatives.contains(key)
+    }
+}
+
+/// The tombstones of the missing keys, with the instant they were recorded.
+pub(crate) struct Negatives<K> {
+    /// Whether a tombstone was ever recorded, to keep inserts lock free otherwise.
+    any: AtomicBool,
+    entries: Mutex<HashMap<K, Instant>>,
+    pub ttl: Option<Duration>,
+}
+
+impl<K> Default for Negatives<K> {
+    fn default() -> Self {
+        Negatives {
+            any: AtomicBool::new(false),
+            entries: Mutex::new(HashMap::new()),
+            ttl: None,
+        }
+    }
+}
+
+impl<K> Negatives<K>
+where
+    K: Clone + Eq + Hash,
+{
+    fn contains(&self, key: &K) -> bool {
+        let mut entries = self.entries.lock();
+
+        match entries.get(key) {
+            Some(at) if self.expired(*at) => {
+                entries.remove(key);
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn expired(&self, at: Instant) -> bool {
+        self.ttl.is_some_and(|ttl| at.elapsed() >= ttl)
+    }
+
+    fn insert(&self, key: K, capacity: usize) {
+        self.any.store(true, Relaxed);
+        let mut entries = self.entries.lock();
+        entries.insert(key, Instant::now());
+
+        if entries.len() > capacity {
+            entries.retain(|_, at| !self.expired(*at));
+        }
+
+        if entries.len() > capacity {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, at)| **at)
+                .map(|(k, _)| k.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn remove(&self, key: &K) {
+        if !self.any.load(Relaxed) {
+            return;
+        }
+
+        let mut entries = self.entries.lock();
+
+        if !entries.is_empty() {
+            entries.remove(key);
+        }
+    }
+}
+
+#[test]
+fn test_get_or_maybe_init() {
+    use std::{cell::Cell, thread::sleep};
+
+    let map = FixedSizeLruMap::builder(2)
+        .negative_ttl(Duration::from_millis(20))
+        .build();
+    let lookups = Cell::new(0);
+    let lookup = || {
+        lookups.set(lookups.get() + 1);
+        None
+    };
+
+    assert!(map.get_or_maybe_init("a", lookup).is_none());
+    assert!(map.get_or_maybe_init("a", lookup).is_none());
+    assert_eq!(1, lookups.get());
+
+    sleep(Duration::from_millis(30));
+    assert!(map.get_or_maybe_init("a", lookup).is_none());
+    assert_eq!(2, lookups.get());
+
+    map.insert("a", 1);
+    assert!(!map.is_negative(&"a"));
+    assert_eq!(Some(1), map.get_or_maybe_init("a", lookup).map(|v| *v));
+}
+
+#[test]
+fn test_inserts_skip_negatives() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    map.insert("a", 1);
+
+    // without any tombstone, an insert does not lock them.
+    let entries = map.negatives.entries.lock();
+    map.insert("b", 2);
+    drop(entries);
+
+    assert!(map.get_or_maybe_init("c", || None).is_none());
+    map.insert("c", 3);
+    assert!(!map.is_negative(&"c"));
+}
@@ -0,0 +1,174 @@
+//! Parallel processing over a snapshot of the entries.
+use crate::{FixedSizeLruMap, MapGuard};
+use std::{
+    hash::{BuildHasher, Hash},
+    sync::{
+        atomic::{AtomicUsize, Ordering::Relaxed},
+        Arc,
+    },
+    thread::{available_parallelism, scope},
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash + Send + Sync,
+    V: Send + Sync,
+    S: BuildHasher + Send + Sync,
+{
+    /// Calls `f` on every entry, spreading the work across all available cores.
+    ///
+    /// The entries are snapshotted first, so the map is not locked while `f` runs.
+    pub fn par_for_each<F>(&self, f: F)
+    where
+        F: Fn(&K, &MapGuard<V>) + Sync,
+    {
+        par_chunks(&self.entries(), |chunk| {
+            for (k, v) in chunk {
+                f(k, v);
+            }
+        });
+    }
+
+    /// Removes every entry for which `f` returns false, evaluating `f` in parallel.
+    ///
+    /// Entries replaced while `f` was running are kept, only the values `f` has
+    /// rejected are removed.
+    pub fn par_retain<F>(&self, f: F)
+    where
+        F: Fn(&K, &MapGuard<V>) -> bool + Sync,
+    {
+        let entries = self.entries();
+        let rejected = parking_lot::Mutex::new(Vec::new());
+
+        par_chunks(&entries, |chunk| {
+            let r = chunk
+                .iter()
+                .filter(|(k, v)| !f(k, v))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            rejected.lock().extend(r);
+        });
+
+        // going through `remove_if` cascades to the dependents and tells the
+        // listeners, as any explicit removal.
+        for (k, v) in rejected.into_inner() {
+            self.remove_if(&k, |cur| MapGuard::ptr_eq(cur, &v));
+        }
+    }
+
+    /// Loads the values of the missing `keys` across all available cores.
+    ///
+    /// The loaded values are inserted in batches to limit the number of write
+    /// lock acquisitions. Keys inserted by someone else while loading are left
+    /// untouched. Returns the number of inserted values.
+    pub fn warm_up_parallel<I, F>(&self, keys: I, loader: F) -> usize
+    where
+        I: IntoIterator<Item = K>,
+        F: Fn(&K) -> V + Sync,
+    {
+        let keys = {
+            let map = self.map.read();
+            keys.into_iter()
+                .filter(|k| !map.contains_key(k))
+                .collect::<Vec<_>>()
+        };
+
+        let inserted = AtomicUsize::new(0);
+
+        par_chunks(&keys, |chunk| {
+            for batch in chunk.chunks(WARM_UP_BATCH) {
+                let values = batch
+                    .iter()
+                    .map(|k| (k.clone(), self.load(k, || loader(k))))
+                    .collect::<Vec<_>>();
+
+                let mut map = self.map.write();
+                let mut notices = Vec::new();
+
+                for (k, v) in values {
+                    if !map.contains_key(&k) {
+                        self.insert_locked(&mut map, k, Arc::new(v), &mut notices);
+                        inserted.fetch_add(1, Relaxed);
+                    }
+                }
+
+                drop(map);
+                self.notify(notices);
+            }
+        });
+
+        inserted.into_inner()
+    }
+}
+
+const WARM_UP_BATCH: usize = 64;
+
+fn par_chunks<T, F>(items: &[T], f: F)
+where
+    T: Sync,
+    F: Fn(&[T]) + Sync,
+{
+    let threads = available_parallelism().map_or(1, |n| n.get());
+    let size = items.len().div_ceil(threads).max(1);
+
+    if items.len() <= size {
+        f(items);
+        return;
+    }
+
+    scope(|s| {
+        for chunk in items.chunks(size) {
+            s.spawn(|| f(chunk));
+        }
+    });
+}
+
+#[test]
+fn test_par_retain() {
+    let map = FixedSizeLruMap::with_capacity(100);
+
+    for i in 0..100 {
+        map.insert(i, i * 2);
+    }
+
+    map.par_retain(|k, _| k % 2 == 0);
+    assert_eq!(50, map.len());
+
+    let sum = AtomicUsize::new(0);
+    map.par_for_each(|_, v| {
+        sum.fetch_add(**v, Relaxed);
+    });
+    assert_eq!(
+        (0..100).step_by(2).map(|i| i * 2).sum::<usize>(),
+        sum.into_inner()
+    );
+}
+
+#[test]
+fn test_warm_up_parallel() {
+    let map = FixedSizeLruMap::with_capacity(1000);
+    map.insert(3, 0);
+
+    assert_eq!(999, map.warm_up_parallel(0..1000, |k| k * 10));
+    assert_eq!(1000, map.len());
+    assert_eq!(0, *map.get(&3).unwrap());
+    assert_eq!(50, *map.get(&5).unwrap());
+}
+
+#[test]
+fn test_par_retain_notifies() {
+    let map = FixedSizeLruMap::with_capacity(10);
+    let mut events = map.events();
+    map.insert(1, ());
+    map.insert(2, ());
+    map.depends_on(2, 1);
+
+    map.par_retain(|k, _| *k != 1);
+    assert!(map.is_empty());
+
+    let removed = std::iter::from_fn(|| events.try_recv())
+        .filter(|e| matches!(e, crate::CacheEvent::Remove(..)))
+        .count();
+    assert_eq!(2, removed);
+}
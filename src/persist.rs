@@ -0,0 +1,504 @@
+//! Saving of snapshots to files.
+use crate::{
+    maintenance::spawn_periodic, CacheSnapshot, FixedSizeLruMap, MaintenanceHandle, MapGuard,
+};
+use std::{
+    fs::{self, File},
+    hash::{BuildHasher, Hash},
+    io::{self, BufReader, BufWriter, Error, ErrorKind, Read, Write},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering::Relaxed},
+        Arc,
+    },
+    time::Duration,
+};
+
+const MAGIC: &[u8; 8] = b"FSLRUMAP";
+const VERSION: u8 = 1;
+
+/// A type that can be saved in a snapshot file.
+pub trait Persist: Sized {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()>;
+    fn read_from(r: &mut dyn Read) -> io::Result<Self>;
+}
+
+macro_rules! persist_int {
+    ($($t:ty),*) => {$(
+        impl Persist for $t {
+            fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+                w.write_all(&self.to_le_bytes())
+            }
+
+            fn read_from(r: &mut dyn Read) -> io::Result<Self> {
+                let mut bytes = [0; std::mem::size_of::<$t>()];
+                r.read_exact(&mut bytes)?;
+                Ok(<$t>::from_le_bytes(bytes))
+            }
+        }
+    )*};
+}
+
+persist_int!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, f32, f64);
+
+impl Persist for usize {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        (*self as u64).write_to(w)
+    }
+
+    fn read_from(r: &mut dyn Read) -> io::Result<Self> {
+        usize::try_from(u64::read_from(r)?).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+impl Persist for bool {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        u8::from(*self).write_to(w)
+    }
+
+    fn read_from(r: &mut dyn Read) -> io::Result<Self> {
+        Ok(u8::read_from(r)? != 0)
+    }
+}
+
+impl Persist for char {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        u32::from(*self).write_to(w)
+    }
+
+    fn read_from(r: &mut dyn Read) -> io::Result<Self> {
+        char::from_u32(u32::read_from(r)?).ok_or_else(|| invalid("invalid char"))
+    }
+}
+
+impl Persist for String {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.len().write_to(w)?;
+        w.write_all(self.as_bytes())
+    }
+
+    fn read_from(r: &mut dyn Read) -> io::Result<Self> {
+        let len = usize::read_from(r)?;
+        let mut bytes = Vec::new();
+        r.take(len as u64).read_to_end(&mut bytes)?;
+
+        if bytes.len() != len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+
+        String::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+impl<T: Persist> Persist for Option<T> {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.is_some().write_to(w)?;
+        self.as_ref().map_or(Ok(()), |v| v.write_to(w))
+    }
+
+    fn read_from(r: &mut dyn Read) -> io::Result<Self> {
+        Ok(match bool::read_from(r)? {
+            true => Some(T::read_from(r)?),
+            false => None,
+        })
+    }
+}
+
+impl<T: Persist> Persist for Vec<T> {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.len().write_to(w)?;
+        self.iter().try_for_each(|v| v.write_to(w))
+    }
+
+    fn read_from(r: &mut dyn Read) -> io::Result<Self> {
+        let len = usize::read_from(r)?;
+
+        // do not trust the length for the allocation, the file may be corrupted.
+        let mut items = Vec::with_capacity(len.min(1024));
+
+        for _ in 0..len {
+            items.push(T::read_from(r)?);
+        }
+
+        Ok(items)
+    }
+}
+
+impl<A: Persist, B: Persist> Persist for (A, B) {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.0.write_to(w)?;
+        self.1.write_to(w)
+    }
+
+    fn read_from(r: &mut dyn Read) -> io::Result<Self> {
+        Ok((A::read_from(r)?, B::read_from(r)?))
+    }
+}
+
+/// Writes the value of the guard, so it can be saved without being cloned out first.
+///
+/// A guard read back is detached from any map.
+impl<V: Persist> Persist for MapGuard<V> {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        V::write_to(self, w)
+    }
+
+    fn read_from(r: &mut dyn Read) -> io::Result<Self> {
+        Ok(MapGuard::new(0, V::read_from(r)?))
+    }
+}
+
+impl<K: Persist, V: Persist> CacheSnapshot<K, V> {
+    /// Reads a snapshot written by [`write_to`](Self::write_to), checking its format.
+    pub fn read_from<R: Read>(r: R) -> io::Result<Self> {
+        let reader = SnapshotReader::new(r)?;
+        let capacity = reader.capacity();
+        let entries = reader.collect::<io::Result<Vec<_>>>()?;
+        Ok(CacheSnapshot { capacity, entries })
+    }
+
+    pub fn write_to<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut w = BufWriter::new(w);
+        w.write_all(MAGIC)?;
+        VERSION.write_to(&mut w)?;
+        self.capacity.write_to(&mut w)?;
+        self.entries.write_to(&mut w)?;
+        w.flush()
+    }
+}
+
+/// Reads the entries of a snapshot one at a time, from the least to the most
+/// recently used.
+///
+/// Restoring a very large snapshot through it never holds more than one
+/// decoded entry besides the map itself.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::{FixedSizeLruMap, SnapshotReader};
+///
+/// let mut file = Vec::new();
+/// let map = FixedSizeLruMap::with_capacity(10);
+/// map.insert(1u32, 1u64);
+/// map.snapshot().write_to(&mut file).unwrap();
+///
+/// let reader = SnapshotReader::new(&file[..]).unwrap();
+/// let restored = FixedSizeLruMap::with_capacity(reader.capacity());
+///
+/// for entry in reader {
+///     let (k, v): (u32, u64) = entry.unwrap();
+///     restored.insert(k, v);
+/// }
+///
+/// assert_eq!(1, restored.len());
+/// ```
+pub struct SnapshotReader<K, V, R> {
+    capacity: usize,
+    r: BufReader<R>,
+    remaining: usize,
+    _kv: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> SnapshotReader<K, V, File> {
+    /// Opens the snapshot file saved in `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<K, V, R: Read> SnapshotReader<K, V, R> {
+    /// Reads the header of the snapshot, checking its format.
+    pub fn new(r: R) -> io::Result<Self> {
+        let mut r = BufReader::new(r);
+        let mut magic = [0; MAGIC.len()];
+        r.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(invalid("not a snapshot file"));
+        }
+
+        if u8::read_from(&mut r)? != VERSION {
+            return Err(invalid("unsupported snapshot version"));
+        }
+
+        let capacity = usize::read_from(&mut r)?;
+        let remaining = usize::read_from(&mut r)?;
+
+        if remaining > capacity {
+            return Err(invalid("more entries than the capacity"));
+        }
+
+        Ok(SnapshotReader {
+            capacity,
+            r,
+            remaining,
+            _kv: PhantomData,
+        })
+    }
+
+    /// Returns the capacity of the map the snapshot was taken from.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl<K: Persist, V: Persist, R: Read> Iterator for SnapshotReader<K, V, R> {
+    type Item = io::Result<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let entry = <(K, V)>::read_from(&mut self.r);
+
+        // stop at the first error, what follows cannot be decoded.
+        self.remaining = match entry {
+            Ok(_) => self.remaining - 1,
+            Err(_) => 0,
+        };
+
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash + Persist,
+    V: Persist,
+    S: BuildHasher,
+{
+    /// Restores the snapshot saved in `path` by [`save_to`](Self::save_to).
+    ///
+    /// Fails without touching the map if the file is not a snapshot of a map
+    /// of the same capacity.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let path = std::env::temp_dir().join("fixed_size_lru_map_doc.snapshot");
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// map.insert(1, "a".to_owned());
+    /// map.save_to(&path).unwrap();
+    ///
+    /// let restored = FixedSizeLruMap::<u32, String>::with_capacity(10);
+    /// restored.load_from(&path).unwrap();
+    /// assert_eq!("a", *restored.get(&1).unwrap());
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    pub fn load_from<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let snapshot = CacheSnapshot::read_from(File::open(path)?)?;
+
+        if snapshot.capacity != self.capacity {
+            return Err(invalid(
+                "the snapshot was taken from a map of another capacity",
+            ));
+        }
+
+        self.restore(snapshot);
+        Ok(())
+    }
+
+    /// Saves a [`snapshot`](Self::snapshot) of the map in `path`.
+    ///
+    /// The snapshot is written next to `path` then renamed over it, so a crash
+    /// while saving leaves the previous snapshot intact.
+    pub fn save_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()>
+    where
+        V: Clone,
+    {
+        replace_file(path.as_ref(), |file| self.snapshot().write_to(file))
+    }
+
+    /// Saves the map in `path` every `interval` on a background thread, provided
+    /// it was mutated at least `min_mutations` times since the last save.
+    ///
+    /// A failed save is retried on the next tick. The thread does not keep the
+    /// map alive, it stops once the map or the returned handle is dropped.
+    pub fn spawn_snapshots<P>(
+        self: &Arc<Self>,
+        path: P,
+        interval: Duration,
+        min_mutations: u64,
+    ) -> MaintenanceHandle
+    where
+        K: Send + Sync + 'static,
+        P: Into<PathBuf>,
+        S: Send + Sync + 'static,
+        V: Clone + Send + Sync + 'static,
+    {
+        let path = path.into();
+
+        // only the count matters, a single queued event is enough.
+        let mut events = self.subscribe(1);
+        let mut dropped = 0;
+        let mut pending = 0;
+
+        spawn_periodic(self, interval, move |map| {
+            while events.try_recv().is_some() {
+                pending += 1;
+            }
+
+            pending += events.dropped() - dropped;
+            dropped = events.dropped();
+
+            if pending >= min_mutations.max(1) && map.save_to(&path).is_ok() {
+                pending = 0;
+            }
+        })
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Replaces `path` by a file written by `write`, so a crash leaves either the
+/// previous or the new content, never a mix.
+///
+/// The file is written under a name unique to the call then renamed over
+/// `path`, and the rename is synced to the directory so it survives a crash.
+pub(crate) fn replace_file<F>(path: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        NEXT.fetch_add(1, Relaxed)
+    ));
+    let tmp = PathBuf::from(tmp);
+
+    let written = File::create(&tmp)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_data()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+
+    sync_dir(path)
+}
+
+/// Flushes the directory entry of `path`, e.g. after a rename.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened for syncing through std on other platforms.
+#[cfg(not(unix))]
+fn sync_dir(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[test]
+fn test_save_load() {
+    let path = std::env::temp_dir().join(format!(
+        "fixed_size_lru_map_{}.snapshot",
+        std::process::id()
+    ));
+    let map = FixedSizeLruMap::with_capacity(3);
+    let (a, _) = map.insert("a".to_owned(), (1u8, Some('x')));
+    map.insert("b".to_owned(), (2u8, None));
+    map.save_to(&path).unwrap();
+
+    let mut bytes = Vec::new();
+    a.write_to(&mut bytes).unwrap();
+    assert_eq!(
+        *a,
+        *MapGuard::<(u8, Option<char>)>::read_from(&mut &bytes[..]).unwrap()
+    );
+
+    let restored = FixedSizeLruMap::with_capacity(3);
+    restored.load_from(&path).unwrap();
+    assert_eq!(map.snapshot(), restored.snapshot());
+
+    let other = FixedSizeLruMap::<String, (u8, Option<char>)>::with_capacity(4);
+    assert_eq!(
+        ErrorKind::InvalidData,
+        other.load_from(&path).unwrap_err().kind()
+    );
+
+    std::fs::write(&path, b"garbage").unwrap();
+    assert!(restored.load_from(&path).is_err());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_spawn_snapshots() {
+    let path = std::env::temp_dir().join(format!(
+        "fixed_size_lru_map_{}.periodic",
+        std::process::id()
+    ));
+    let map = Arc::new(FixedSizeLruMap::with_capacity(10));
+    let handle = map.spawn_snapshots(&path, Duration::from_millis(10), 2);
+
+    map.insert(1u8, 1u8);
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(!path.exists());
+
+    map.insert(2, 2);
+    std::thread::sleep(Duration::from_millis(50));
+    drop(handle);
+
+    let restored = FixedSizeLruMap::<u8, u8>::with_capacity(10);
+    restored.load_from(&path).unwrap();
+    assert_eq!(2, restored.len());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_concurrent_saves() {
+    use std::thread::scope;
+
+    let path =
+        std::env::temp_dir().join(format!("fixed_size_lru_map_{}.concurrent", process::id()));
+
+    let map = FixedSizeLruMap::with_capacity(10);
+    map.insert(1u32, "a".to_owned());
+
+    // each save writes its own temporary file, none fails on another's rename.
+    scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| map.save_to(&path).unwrap());
+        }
+    });
+
+    let loaded = FixedSizeLruMap::<u32, String>::with_capacity(10);
+    loaded.load_from(&path).unwrap();
+    assert_eq!("a", *loaded.get(&1).unwrap());
+
+    let dir = path.parent().unwrap();
+    let name = path.file_name().unwrap().to_str().unwrap();
+    let leftovers = fs::read_dir(dir)
+        .unwrap()
+        .filter(|e| {
+            let e = e.as_ref().unwrap().file_name();
+            let e = e.to_str().unwrap();
+            e.starts_with(name) && e.ends_with(".tmp")
+        })
+        .count();
+
+    fs::remove_file(&path).unwrap();
+    assert_eq!(0, leftovers);
+}
@@ -0,0 +1,57 @@
+use crate::{Entry, MapGuard};
+use parking_lot::Mutex;
+use std::{collections::VecDeque, sync::Arc};
+
+/// Recycles the allocations of entries that left the map.
+///
+/// Entries are retired here when they are evicted, replaced or removed. Once
+/// every outside guard is dropped, the pool holds the last reference and the
+/// allocation is reused for the next inserted value. The retired value itself
+/// is only dropped at that point, or when the node is pushed out of the pool.
+pub(crate) struct EntryPool<V> {
+    capacity: usize,
+    nodes: Mutex<VecDeque<Arc<Entry<V>>>>,
+}
+
+impl<V> EntryPool<V> {
+    pub fn new(capacity: usize) -> Self {
+        EntryPool {
+            capacity,
+            nodes: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn retire(&self, guard: &MapGuard<V>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut nodes = self.nodes.lock();
+
+        if nodes.len() == self.capacity {
+            nodes.pop_front();
+        }
+
+        nodes.push_back(Arc::clone(&guard.0));
+    }
+
+    /// Builds a guard for `value`, reusing a retired node when one is no longer shared.
+    pub fn guard(&self, age: u64, value: Arc<V>) -> MapGuard<V> {
+        let mut nodes = self.nodes.lock();
+
+        // only look at the oldest node to keep inserts O(1); a node still
+        // shared goes to the back and gets another chance later.
+        if let Some(mut node) = nodes.pop_front() {
+            match Arc::get_mut(&mut node) {
+                Some(inner) => {
+                    *inner = Entry::new(age, value);
+                    return MapGuard(node);
+                }
+                None => nodes.push_back(node),
+            }
+        }
+
+        drop(nodes);
+        MapGuard::from_arc(age, value)
+    }
+}
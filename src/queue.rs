@@ -0,0 +1,113 @@
+//! A bounded list of items ordered by recency.
+use crate::{FixedSizeLruSet, MapGuard};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+/// Distinct items ordered from the most to the least recently pushed, dropping
+/// the oldest ones beyond the capacity, e.g. for recently viewed lists or MRU menus.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::LruQueue;
+///
+/// let recent = LruQueue::with_capacity(3);
+/// for page in ["home", "docs", "blog", "docs", "about"] {
+///     recent.push(page);
+/// }
+///
+/// assert_eq!(vec!["about", "docs", "blog"], recent.to_vec());
+/// assert_eq!(Some("blog"), recent.pop_oldest());
+/// ```
+pub struct LruQueue<T, S = RandomState> {
+    set: FixedSizeLruSet<T, S>,
+}
+
+impl<T> LruQueue<T>
+where
+    T: Clone + Eq + Hash,
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        LruQueue {
+            set: FixedSizeLruSet::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T, S> LruQueue<T, S>
+where
+    T: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn capacity(&self) -> usize {
+        self.set.capacity()
+    }
+
+    /// Returns whether `item` is in the queue, without changing its position.
+    pub fn contains(&self, item: &T) -> bool {
+        self.set.map().contains_key(item)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Removes and returns the least recently pushed item.
+    pub fn pop_oldest(&self) -> Option<T> {
+        let map = self.set.map();
+
+        loop {
+            let (item, guard) = {
+                let view = map.view();
+                let (item, guard) = view.iter().min_by_key(|(_, v)| v.age())?;
+                (item.clone(), guard.clone())
+            };
+
+            // removed and pushed again in between, look for the oldest one again.
+            if map
+                .remove_if(&item, |v| MapGuard::ptr_eq(v, &guard))
+                .is_some()
+            {
+                return Some(item);
+            }
+        }
+    }
+
+    /// Moves `item` to the front, adding it if missing and dropping the oldest
+    /// item when full. Returns false if it was already in the queue.
+    pub fn push(&self, item: T) -> bool {
+        self.set.insert(item)
+    }
+
+    pub fn remove(&self, item: &T) -> bool {
+        self.set.remove(item)
+    }
+
+    /// Returns the items from the most to the least recently pushed.
+    pub fn to_vec(&self) -> Vec<T> {
+        let mut items = self.set.map().export_keys_by_recency();
+        items.reverse();
+        items
+    }
+}
+
+#[test]
+fn test_lru_queue() {
+    let queue = LruQueue::with_capacity(2);
+    assert!(queue.push(1));
+    assert!(queue.push(2));
+    assert!(!queue.push(1));
+    assert!(queue.push(3));
+
+    assert_eq!(vec![3, 1], queue.to_vec());
+    assert!(!queue.contains(&2));
+
+    assert_eq!(Some(1), queue.pop_oldest());
+    assert_eq!(Some(3), queue.pop_oldest());
+    assert_eq!(None, queue.pop_oldest());
+}
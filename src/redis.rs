@@ -0,0 +1,251 @@
+//! A [`RemoteTier`] storing the entries in Redis.
+use crate::{persist::Persist, RemoteTier};
+use parking_lot::Mutex;
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    marker::PhantomData,
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// A connection to a Redis server, the keys and values encoded with [`Persist`].
+///
+/// The connection is opened lazily and reopened after an error, so a restart
+/// of the server only fails the calls made while it is down. A server not
+/// answering within the [`timeout`](Self::timeout) fails the call and drops the
+/// connection, so an unanswered reply is never read as the next one.
+pub struct RedisTier<K, V> {
+    addr: String,
+    conn: Mutex<Option<BufReader<TcpStream>>>,
+    prefix: Vec<u8>,
+    timeout: Duration,
+    ttl: Option<Duration>,
+    _kv: PhantomData<fn(K, V)>,
+}
+
+/// The replies of the commands used, only bulk strings carry data.
+enum Reply {
+    Bulk(Option<Vec<u8>>),
+    Integer,
+    Simple,
+}
+
+impl<K, V> RedisTier<K, V>
+where
+    K: Persist,
+    V: Persist,
+{
+    /// Connects to the server at `addr`, e.g. `127.0.0.1:6379`, prefixing the
+    /// keys with `prefix` so several caches can share a server.
+    pub fn new(addr: &str, prefix: &str) -> Self {
+        RedisTier {
+            addr: addr.to_owned(),
+            conn: Mutex::new(None),
+            prefix: prefix.as_bytes().to_owned(),
+            timeout: Duration::from_secs(5),
+            ttl: None,
+            _kv: PhantomData,
+        }
+    }
+
+    /// Sets how long to wait for connecting, sending and each reply, 5 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Lets the server expire the entries `ttl` after they were written.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn command(&self, args: &[&[u8]]) -> io::Result<Reply> {
+        let mut conn = self.conn.lock();
+
+        let stream = match &mut *conn {
+            Some(stream) => stream,
+            None => conn.insert(BufReader::new(connect(&self.addr, self.timeout)?)),
+        };
+
+        let reply = send(stream, args);
+
+        if reply.is_err() {
+            *conn = None;
+        }
+
+        reply
+    }
+
+    fn key(&self, key: &K) -> io::Result<Vec<u8>> {
+        let mut bytes = self.prefix.clone();
+        key.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<K, V> RemoteTier<K, V> for RedisTier<K, V>
+where
+    K: Persist,
+    V: Persist,
+{
+    type Error = io::Error;
+
+    fn get(&self, key: &K) -> io::Result<Option<V>> {
+        match self.command(&[b"GET", &self.key(key)?])? {
+            Reply::Bulk(Some(bytes)) => V::read_from(&mut &bytes[..]).map(Some),
+            Reply::Bulk(None) => Ok(None),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn remove(&self, key: &K) -> io::Result<()> {
+        match self.command(&[b"DEL", &self.key(key)?])? {
+            Reply::Integer => Ok(()),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn set(&self, key: &K, value: &V) -> io::Result<()> {
+        let key = self.key(key)?;
+        let mut bytes = Vec::new();
+        value.write_to(&mut bytes)?;
+
+        let reply = match self.ttl {
+            Some(ttl) => {
+                let millis = ttl.as_millis().max(1).to_string();
+                self.command(&[b"SET", &key, &bytes, b"PX", millis.as_bytes()])?
+            }
+            None => self.command(&[b"SET", &key, &bytes])?,
+        };
+
+        match reply {
+            Reply::Simple => Ok(()),
+            _ => Err(unexpected()),
+        }
+    }
+}
+
+fn connect(addr: &str, timeout: Duration) -> io::Result<TcpStream> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unresolved redis address"))?;
+
+    let stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(stream)
+}
+
+/// Sends a command in the RESP protocol and reads its reply.
+fn send(stream: &mut BufReader<TcpStream>, args: &[&[u8]]) -> io::Result<Reply> {
+    let mut request = format!("*{}\r\n", args.len()).into_bytes();
+
+    for arg in args {
+        request.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        request.extend_from_slice(arg);
+        request.extend_from_slice(b"\r\n");
+    }
+
+    stream.get_mut().write_all(&request)?;
+
+    let mut line = String::new();
+    stream.read_line(&mut line)?;
+
+    let line = line.strip_suffix("\r\n").ok_or_else(unexpected)?;
+    let (kind, rest) = line.split_at_checked(1).ok_or_else(unexpected)?;
+
+    match kind {
+        "+" => Ok(Reply::Simple),
+        "-" => Err(io::Error::other(rest.to_owned())),
+        ":" => Ok(Reply::Integer),
+        "$" => {
+            let Ok(len) = usize::try_from(rest.parse::<i64>().map_err(|_| unexpected())?) else {
+                return Ok(Reply::Bulk(None));
+            };
+
+            let mut bytes = vec![0; len + 2];
+            stream.read_exact(&mut bytes)?;
+            bytes.truncate(len);
+            Ok(Reply::Bulk(Some(bytes)))
+        }
+        _ => Err(unexpected()),
+    }
+}
+
+fn unexpected() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "unexpected redis reply")
+}
+
+#[test]
+fn test_redis_tier() {
+    use crate::RemoteTieredCache;
+    use std::{collections::HashMap, net::TcpListener, thread::spawn};
+
+    // a fake server answering GET, SET and DEL.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut stream = BufReader::new(stream);
+        let mut store = HashMap::<Vec<u8>, Vec<u8>>::new();
+        let mut line = String::new();
+
+        while stream.read_line(&mut line).unwrap() > 0 {
+            let count: usize = line.trim()[1..].parse().unwrap();
+            let mut args = Vec::new();
+
+            for _ in 0..count {
+                line.clear();
+                stream.read_line(&mut line).unwrap();
+                let mut arg = vec![0; line.trim()[1..].parse::<usize>().unwrap() + 2];
+                stream.read_exact(&mut arg).unwrap();
+                arg.truncate(arg.len() - 2);
+                args.push(arg);
+            }
+
+            let reply = match &args[0][..] {
+                b"GET" => match store.get(&args[1]) {
+                    Some(v) => [format!("${}\r\n", v.len()).as_bytes(), v, b"\r\n"].concat(),
+                    None => b"$-1\r\n".to_vec(),
+                },
+                b"SET" => {
+                    store.insert(args[1].clone(), args[2].clone());
+                    b"+OK\r\n".to_vec()
+                }
+                b"DEL" => format!(":{}\r\n", store.remove(&args[1]).is_some() as u8).into_bytes(),
+                _ => b"-ERR unknown command\r\n".to_vec(),
+            };
+
+            stream.get_mut().write_all(&reply).unwrap();
+            line.clear();
+        }
+    });
+
+    let cache = RemoteTieredCache::with_capacity(1, RedisTier::new(&addr, "test:"));
+    cache.insert(1u32, "a".to_owned()).unwrap();
+    cache.insert(2, "b".to_owned()).unwrap();
+
+    // 1 was evicted locally but is still found remotely.
+    assert!(!cache.map().contains_key(&1));
+    assert_eq!("a", *cache.get(&1).unwrap().unwrap());
+
+    cache.remove(&1).unwrap();
+    assert!(cache.get(&1).unwrap().is_none());
+}
+
+#[test]
+fn test_redis_timeout() {
+    use std::net::TcpListener;
+
+    // a server accepting the connection but never answering.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let tier = RedisTier::<u32, u32>::new(&addr, "test:").timeout(Duration::from_millis(50));
+    assert!(tier.get(&1).is_err());
+    assert!(tier.conn.lock().is_none());
+}
@@ -0,0 +1,191 @@
+//! Refresh-ahead loading keeping the hot entries fresh in the background.
+use crate::{FixedSizeLruMap, MapGuard};
+use parking_lot::{Condvar, Mutex};
+use std::{
+    collections::VecDeque,
+    hash::{BuildHasher, Hash},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::Ordering::{AcqRel, Relaxed, Release},
+        Arc, OnceLock,
+    },
+    thread,
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash + Send + Sync + 'static,
+    V: Send + Sync + 'static,
+    S: BuildHasher + Send + Sync + 'static,
+{
+    /// Gets the value of `key` or initializes it with `f`, like [`get_or_init`](Self::get_or_init).
+    ///
+    /// When the value is older than the [`refresh_after`](crate::FixedSizeLruMapBuilder::refresh_after)
+    /// threshold, it is still returned but `f` reloads it in the background, so
+    /// the callers never wait on a revalidation. The reloads of every map share a
+    /// fixed number of threads, one per core, and queue up past that.
+    pub fn get_or_refresh<F>(self: &Arc<Self>, key: K, f: F) -> MapGuard<V>
+    where
+        F: FnOnce() -> V + Send + 'static,
+    {
+        let Some(value) = self.get(&key) else {
+            let value = self.load(&key, f);
+            return self.insert(key, value).0;
+        };
+
+        let stale = self
+            .refresh_after
+            .zip(value.0.loaded)
+            .is_some_and(|(after, loaded)| loaded.elapsed() >= after);
+
+        // only the first caller seeing the stale value triggers the reload.
+        if stale
+            && value
+                .0
+                .refreshing
+                .compare_exchange(false, true, AcqRel, Relaxed)
+                .is_ok()
+        {
+            let map = Arc::clone(self);
+            let old = Refreshing(value.clone());
+
+            Refresher::shared().submit(Box::new(move || {
+                let new = map.load(&key, f);
+                let old = &old.0;
+                let mut entries = map.map.write();
+                let mut notices = Vec::new();
+
+                // do not resurrect an entry removed or replaced meanwhile.
+                if entries
+                    .get(&key)
+                    .is_some_and(|cur| MapGuard::ptr_eq(cur, old))
+                {
+                    map.insert_locked(&mut entries, key, Arc::new(new), &mut notices);
+                }
+
+                drop(entries);
+                map.notify(notices);
+            }));
+        }
+
+        value
+    }
+}
+
+/// Lets the stale value be refreshed again once the reload is over, even if it panicked.
+struct Refreshing<V>(MapGuard<V>);
+
+impl<V> Drop for Refreshing<V> {
+    fn drop(&mut self) {
+        self.0 .0.refreshing.store(false, Release);
+    }
+}
+
+/// A reload queued by [`get_or_refresh`](FixedSizeLruMap::get_or_refresh).
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed set of threads running the reloads, instead of one thread per stale read.
+struct Refresher {
+    jobs: Mutex<VecDeque<Job>>,
+    queued: Condvar,
+}
+
+impl Refresher {
+    fn shared() -> &'static Refresher {
+        static REFRESHER: OnceLock<Refresher> = OnceLock::new();
+
+        REFRESHER.get_or_init(|| {
+            let threads = thread::available_parallelism().map_or(1, usize::from);
+
+            for _ in 0..threads {
+                thread::spawn(|| Refresher::shared().run());
+            }
+
+            Refresher {
+                jobs: Mutex::new(VecDeque::new()),
+                queued: Condvar::new(),
+            }
+        })
+    }
+
+    /// Queues a reload. A stale entry is reloaded once at a time, so the queue
+    /// holds at most one job per entry.
+    fn submit(&self, job: Job) {
+        self.jobs.lock().push_back(job);
+        self.queued.notify_one();
+    }
+
+    fn run(&self) {
+        loop {
+            let job = {
+                let mut jobs = self.jobs.lock();
+
+                loop {
+                    match jobs.pop_front() {
+                        Some(job) => break job,
+                        None => self.queued.wait(&mut jobs),
+                    }
+                }
+            };
+
+            // a panicking loader fails its reload, not the thread.
+            let _ = catch_unwind(AssertUnwindSafe(job));
+        }
+    }
+}
+
+#[test]
+fn test_get_or_refresh() {
+    use std::{thread::sleep, time::Duration};
+
+    let map = Arc::new(
+        FixedSizeLruMap::builder(2)
+            .refresh_after(Duration::from_millis(20))
+            .build(),
+    );
+
+    assert_eq!(1, *map.get_or_refresh("a", || 1));
+    assert_eq!(1, *map.get_or_refresh("a", || 2));
+
+    sleep(Duration::from_millis(30));
+
+    // the stale value is served while the reload runs.
+    assert_eq!(1, *map.get_or_refresh("a", || 3));
+
+    for _ in 0..100 {
+        if *map.get(&"a").unwrap() == 3 {
+            return;
+        }
+
+        sleep(Duration::from_millis(10));
+    }
+
+    panic!("value not refreshed");
+}
+
+#[test]
+fn test_refresh_panic() {
+    use std::{thread::sleep, time::Duration};
+
+    let map = Arc::new(
+        FixedSizeLruMap::builder(2)
+            .refresh_after(Duration::ZERO)
+            .build(),
+    );
+
+    map.insert("a", 1);
+    map.get_or_refresh("a", || panic!("reload failed"));
+
+    // a failed reload does not prevent the next one.
+    for _ in 0..100 {
+        map.get_or_refresh("a", || 2);
+
+        if *map.get(&"a").unwrap() == 2 {
+            return;
+        }
+
+        sleep(Duration::from_millis(10));
+    }
+
+    panic!("value not refreshed");
+}
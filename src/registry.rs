@@ -0,0 +1,95 @@
+//! A process-wide registry listing the caches of an application.
+#[cfg(feature = "stats")]
+use crate::CacheStats;
+use crate::FixedSizeLruMap;
+use parking_lot::Mutex;
+use std::{
+    hash::{BuildHasher, Hash},
+    sync::{Arc, Weak},
+};
+
+static REGISTRY: Mutex<Vec<(String, Weak<dyn Registered>)>> = Mutex::new(Vec::new());
+
+/// What the registry knows about a cache.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegisteredCache {
+    pub capacity: usize,
+    pub len: usize,
+    pub name: String,
+    #[cfg(feature = "stats")]
+    pub stats: CacheStats,
+}
+
+/// A cache that can be listed by the registry.
+trait Registered: Send + Sync {
+    fn describe(&self, name: &str) -> RegisteredCache;
+}
+
+impl<K, V, S> Registered for FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash + Send + Sync,
+    V: Send + Sync,
+    S: BuildHasher + Send + Sync,
+{
+    fn describe(&self, name: &str) -> RegisteredCache {
+        RegisteredCache {
+            capacity: self.capacity,
+            len: self.len(),
+            name: name.to_owned(),
+            #[cfg(feature = "stats")]
+            stats: self.stats(),
+        }
+    }
+}
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash + Send + Sync + 'static,
+    V: Send + Sync + 'static,
+    S: BuildHasher + Send + Sync + 'static,
+{
+    /// Lists the map in the [`registered_caches`] under `name`.
+    ///
+    /// The registry does not keep the map alive, it disappears from the list once dropped.
+    pub fn register(self: &Arc<Self>, name: impl Into<String>) {
+        let map: Weak<dyn Registered> = Arc::downgrade(self) as Weak<dyn Registered>;
+        let mut registry = REGISTRY.lock();
+
+        registry.retain(|(_, cache)| cache.strong_count() > 0);
+        registry.push((name.into(), map));
+    }
+}
+
+/// Describes every registered cache still alive, in registration order.
+pub fn registered_caches() -> Vec<RegisteredCache> {
+    let caches = REGISTRY
+        .lock()
+        .iter()
+        .filter_map(|(name, cache)| Some((name.clone(), cache.upgrade()?)))
+        .collect::<Vec<_>>();
+
+    // describe outside of the registry lock, it takes the lock of every map.
+    caches
+        .iter()
+        .map(|(name, cache)| cache.describe(name))
+        .collect()
+}
+
+#[test]
+fn test_registry() {
+    let users = Arc::new(FixedSizeLruMap::<u32, u32>::with_capacity(10));
+    users.insert(1, 1);
+    users.register("test_registry_users");
+
+    let find = || {
+        registered_caches()
+            .into_iter()
+            .find(|c| c.name == "test_registry_users")
+    };
+
+    let found = find().unwrap();
+    assert_eq!((10, 1), (found.capacity, found.len));
+
+    drop(users);
+    assert!(find().is_none());
+}
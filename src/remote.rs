@@ -0,0 +1,124 @@
+//! A second cache tier shared by several processes, behind the local map.
+use crate::{FixedSizeLruMap, MapGuard};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+/// A remote store (Redis, memcached...) shared by the processes of a fleet.
+pub trait RemoteTier<K, V> {
+    type Error;
+
+    fn get(&self, key: &K) -> Result<Option<V>, Self::Error>;
+    fn remove(&self, key: &K) -> Result<(), Self::Error>;
+    fn set(&self, key: &K, value: &V) -> Result<(), Self::Error>;
+}
+
+/// A cache reading from its local map before falling back to a [`RemoteTier`].
+///
+/// The values found remotely are kept in the local map, the values inserted
+/// locally are written remotely, so each process only computes what no other
+/// process already has.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::{RemoteTier, RemoteTieredCache};
+/// use std::{collections::HashMap, convert::Infallible, sync::Mutex};
+///
+/// #[derive(Default)]
+/// struct Shared(Mutex<HashMap<u32, String>>);
+///
+/// impl RemoteTier<u32, String> for Shared {
+///     type Error = Infallible;
+///
+///     fn get(&self, key: &u32) -> Result<Option<String>, Infallible> {
+///         Ok(self.0.lock().unwrap().get(key).cloned())
+///     }
+///
+///     fn remove(&self, key: &u32) -> Result<(), Infallible> {
+///         self.0.lock().unwrap().remove(key);
+///         Ok(())
+///     }
+///
+///     fn set(&self, key: &u32, value: &String) -> Result<(), Infallible> {
+///         self.0.lock().unwrap().insert(*key, value.clone());
+///         Ok(())
+///     }
+/// }
+///
+/// let cache = RemoteTieredCache::with_capacity(10, Shared::default());
+/// cache.insert(1, "a".to_owned()).unwrap();
+/// cache.map().remove(&1);
+/// assert_eq!("a", *cache.get(&1).unwrap().unwrap());
+/// ```
+pub struct RemoteTieredCache<K, V, R, S = RandomState> {
+    map: FixedSizeLruMap<K, V, S>,
+    remote: R,
+}
+
+impl<K, V, R> RemoteTieredCache<K, V, R>
+where
+    K: Clone + Eq + Hash,
+    R: RemoteTier<K, V>,
+{
+    pub fn with_capacity(capacity: usize, remote: R) -> Self {
+        Self::with_map(FixedSizeLruMap::with_capacity(capacity), remote)
+    }
+}
+
+impl<K, V, R, S> RemoteTieredCache<K, V, R, S>
+where
+    K: Clone + Eq + Hash,
+    R: RemoteTier<K, V>,
+    S: BuildHasher,
+{
+    pub fn with_map(map: FixedSizeLruMap<K, V, S>, remote: R) -> Self {
+        RemoteTieredCache { map, remote }
+    }
+
+    /// Returns the value of `key`, fetching it from the remote tier on a local miss.
+    pub fn get(&self, key: &K) -> Result<Option<MapGuard<V>>, R::Error> {
+        if let Some(v) = self.map.get(key) {
+            return Ok(Some(v));
+        }
+
+        Ok(self
+            .remote
+            .get(key)?
+            .map(|value| self.map.insert(key.clone(), value).0))
+    }
+
+    /// Returns the value of `key`, computing it with `f` when neither tier has it.
+    pub fn get_or_init<F>(&self, key: K, f: F) -> Result<MapGuard<V>, R::Error>
+    where
+        F: FnOnce() -> V,
+    {
+        if let Some(v) = self.get(&key)? {
+            return Ok(v);
+        }
+
+        let value = self.map.load(&key, f);
+        self.insert(key, value)
+    }
+
+    /// Inserts a value in both tiers.
+    pub fn insert(&self, key: K, value: V) -> Result<MapGuard<V>, R::Error> {
+        self.remote.set(&key, &value)?;
+        Ok(self.map.insert(key, value).0)
+    }
+
+    /// Returns the local map.
+    pub fn map(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.map
+    }
+
+    pub fn remote(&self) -> &R {
+        &self.remote
+    }
+
+    /// Removes `key` from both tiers.
+    pub fn remove(&self, key: &K) -> Result<Option<MapGuard<V>>, R::Error> {
+        self.remote.remove(key)?;
+        Ok(self.map.remove(key))
+    }
+}
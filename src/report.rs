@@ -0,0 +1,104 @@
+//! A point in time summary of the map for health endpoints and debug dumps.
+#[cfg(feature = "stats")]
+use crate::CacheStats;
+use crate::FixedSizeLruMap;
+#[cfg(feature = "stats")]
+use std::fmt::Write;
+use std::{
+    hash::{BuildHasher, Hash},
+    time::Duration,
+};
+
+/// Summary of the map returned by [`FixedSizeLruMap::report`].
+///
+/// Every field is plain data, [`CacheReport::to_json`] renders it for a health endpoint.
+/// The ages are only known for a map built with
+/// [`timestamps`](crate::FixedSizeLruMapBuilder::timestamps).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheReport {
+    pub capacity: usize,
+    pub len: usize,
+    /// How long ago the most recently loaded entry was inserted.
+    pub newest: Option<Duration>,
+    /// How long ago the median entry was inserted.
+    pub median: Option<Duration>,
+    /// How long ago the least recently loaded entry was inserted.
+    pub oldest: Option<Duration>,
+    #[cfg(feature = "stats")]
+    pub stats: CacheStats,
+}
+
+impl CacheReport {
+    /// Renders the report as a JSON object, durations being in seconds.
+    pub fn to_json(&self) -> String {
+        let secs =
+            |d: Option<Duration>| d.map_or("null".to_owned(), |d| d.as_secs_f64().to_string());
+        #[allow(unused_mut)]
+        let mut json = format!(
+            r#"{{"capacity":{},"len":{},"newest":{},"median":{},"oldest":{}"#,
+            self.capacity,
+            self.len,
+            secs(self.newest),
+            secs(self.median),
+            secs(self.oldest),
+        );
+
+        #[cfg(feature = "stats")]
+        {
+            let s = &self.stats;
+            let _ = write!(
+                json,
+                r#","stats":{{"evictions":{},"hits":{},"insertions":{},"misses":{},"removals":{},"replacements":{}}}"#,
+                s.evictions, s.hits, s.insertions, s.misses, s.removals, s.replacements,
+            );
+        }
+
+        json.push('}');
+        json
+    }
+}
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Returns a summary of the size, the counters and the age of the entries of the map.
+    pub fn report(&self) -> CacheReport {
+        let map = self.map.read();
+        let len = map.len();
+        let mut ages = map
+            .values()
+            .filter_map(|v| Some(v.0.loaded?.elapsed()))
+            .collect::<Vec<_>>();
+
+        drop(map);
+        ages.sort_unstable();
+
+        CacheReport {
+            capacity: self.capacity,
+            len,
+            newest: ages.first().copied(),
+            median: ages.get(ages.len() / 2).copied(),
+            oldest: ages.last().copied(),
+            #[cfg(feature = "stats")]
+            stats: self.stats(),
+        }
+    }
+}
+
+#[test]
+fn test_report() {
+    let map = FixedSizeLruMap::builder(3).timestamps(true).build();
+    assert_eq!(None, map.report().oldest);
+
+    map.insert(1, 1);
+    std::thread::sleep(Duration::from_millis(5));
+    map.insert(2, 2);
+
+    let report = map.report();
+    assert_eq!((3, 2), (report.capacity, report.len));
+    assert!(report.oldest >= Some(Duration::from_millis(5)));
+    assert!(report.newest < report.oldest);
+    assert!(report.to_json().starts_with(r#"{"capacity":3,"len":2,"#));
+}
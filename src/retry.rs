@@ -0,0 +1,222 @@
+//! Retrying the fallible loaders.
+use parking_lot::{Condvar, Mutex};
+use std::{
+    collections::BTreeMap,
+    future::{poll_fn, Future},
+    sync::{
+        atomic::{AtomicU64, Ordering::Relaxed},
+        Arc, OnceLock,
+    },
+    task::{Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How many times and how often a failed load is retried.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::attempts(3).backoff(Duration::from_millis(1));
+/// let mut calls = 0;
+/// let r = policy.run(|| {
+///     calls += 1;
+///     if calls < 3 { Err("busy") } else { Ok(calls) }
+/// });
+/// assert_eq!(Ok(3), r);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    backoff: Duration,
+    max_attempts: u32,
+    max_backoff: Duration,
+    multiplier: u32,
+}
+
+impl RetryPolicy {
+    /// Tries at most `max_attempts` times, without waiting between the attempts.
+    pub fn attempts(max_attempts: u32) -> Self {
+        RetryPolicy {
+            backoff: Duration::ZERO,
+            max_attempts: max_attempts.max(1),
+            max_backoff: Duration::MAX,
+            multiplier: 1,
+        }
+    }
+
+    /// A single attempt.
+    pub fn none() -> Self {
+        Self::attempts(1)
+    }
+
+    /// Waits `backoff` before the first retry.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Multiplies the wait by `multiplier` after every retry, up to `max_backoff`.
+    pub fn exponential(mut self, multiplier: u32, max_backoff: Duration) -> Self {
+        self.multiplier = multiplier;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Calls `f` until it succeeds or the attempts are exhausted, returning the last error.
+    pub fn run<F, T, E>(&self, mut f: F) -> Result<T, E>
+    where
+        F: FnMut() -> Result<T, E>,
+    {
+        let mut backoff = self.backoff;
+
+        for _ in 1..self.max_attempts {
+            if let Ok(v) = f() {
+                return Ok(v);
+            }
+
+            thread::sleep(backoff);
+            backoff = self.next_backoff(backoff);
+        }
+
+        f()
+    }
+
+    /// Like [`run`](Self::run) for futures. The wait does not block the executor.
+    pub async fn run_async<F, Fut, T, E>(&self, mut f: F) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut backoff = self.backoff;
+
+        for _ in 1..self.max_attempts {
+            if let Ok(v) = f().await {
+                return Ok(v);
+            }
+
+            sleep(backoff).await;
+            backoff = self.next_backoff(backoff);
+        }
+
+        f().await
+    }
+
+    fn next_backoff(&self, backoff: Duration) -> Duration {
+        backoff
+            .saturating_mul(self.multiplier)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+/// Waits `duration` without depending on a particular async runtime.
+async fn sleep(duration: Duration) {
+    if duration.is_zero() {
+        return;
+    }
+
+    let state = Arc::new(Mutex::new((false, None::<Waker>)));
+    Timer::shared().schedule(Instant::now() + duration, Arc::clone(&state));
+
+    poll_fn(|cx| {
+        let mut state = state.lock();
+
+        if state.0 {
+            Poll::Ready(())
+        } else {
+            state.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Whether a sleep is over, and the task to wake when it is.
+type Sleeper = Arc<Mutex<(bool, Option<Waker>)>>;
+
+/// A single thread ending every sleep, instead of one thread per waiting call.
+struct Timer {
+    changed: Condvar,
+    next_id: AtomicU64,
+    sleepers: Mutex<BTreeMap<(Instant, u64), Sleeper>>,
+}
+
+impl Timer {
+    fn shared() -> &'static Timer {
+        static TIMER: OnceLock<Timer> = OnceLock::new();
+
+        TIMER.get_or_init(|| {
+            thread::spawn(|| Timer::shared().run());
+
+            Timer {
+                changed: Condvar::new(),
+                next_id: AtomicU64::new(0),
+                sleepers: Mutex::new(BTreeMap::new()),
+            }
+        })
+    }
+
+    fn schedule(&self, deadline: Instant, sleeper: Sleeper) {
+        let id = self.next_id.fetch_add(1, Relaxed);
+        let mut sleepers = self.sleepers.lock();
+        sleepers.insert((deadline, id), sleeper);
+
+        // only a new earliest deadline shortens the wait of the timer thread.
+        if sleepers.keys().next() == Some(&(deadline, id)) {
+            self.changed.notify_one();
+        }
+    }
+
+    fn run(&self) {
+        let mut sleepers = self.sleepers.lock();
+
+        loop {
+            let now = Instant::now();
+
+            while let Some(entry) = sleepers.first_entry().filter(|e| e.key().0 <= now) {
+                let sleeper = entry.remove();
+                let mut sleeper = sleeper.lock();
+                sleeper.0 = true;
+
+                if let Some(waker) = sleeper.1.take() {
+                    waker.wake();
+                }
+            }
+
+            match sleepers.keys().next() {
+                Some(&(deadline, _)) => drop(self.changed.wait_until(&mut sleepers, deadline)),
+                None => self.changed.wait(&mut sleepers),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_run_async() {
+    let policy = RetryPolicy::attempts(3).backoff(Duration::from_millis(1));
+
+    // the retries of several calls share the timer thread.
+    let results = (0..10)
+        .map(|_| {
+            let mut calls = 0;
+            crate::future::block_on(policy.run_async(|| {
+                calls += 1;
+                let r = if calls < 3 { Err(calls) } else { Ok(calls) };
+                async move { r }
+            }))
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(vec![Ok(3); 10], results);
+}
@@ -0,0 +1,214 @@
+//! A cache split into a young and an old generation.
+use crate::{EventReceiver, FixedSizeLruMap, MapGuard};
+use parking_lot::Mutex;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+/// A segmented lru cache: new entries start in a young map and are promoted to
+/// an old map when read again.
+///
+/// A burst of keys read only once churns through the young map without
+/// evicting the entries proven useful in the old one. The entries evicted from
+/// the old map get a second chance in the young map.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::SegmentedLruMap;
+///
+/// let map = SegmentedLruMap::with_capacity(2, 2);
+/// map.insert("hot", 1);
+/// map.get(&"hot");
+///
+/// // a scan only evicts from the young map.
+/// for key in ["a", "b", "c", "d"] {
+///     map.insert(key, 0);
+/// }
+///
+/// assert!(map.get(&"hot").is_some());
+/// ```
+pub struct SegmentedLruMap<K, V, S = RandomState> {
+    demotions: Mutex<EventReceiver<(K, MapGuard<V>)>>,
+    old: FixedSizeLruMap<K, V, S>,
+    promotions: Mutex<()>,
+    young: FixedSizeLruMap<K, V, S>,
+}
+
+impl<K, V> SegmentedLruMap<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn with_capacity(young_capacity: usize, old_capacity: usize) -> Self {
+        Self::with_maps(
+            FixedSizeLruMap::with_capacity(young_capacity),
+            FixedSizeLruMap::with_capacity(old_capacity),
+        )
+    }
+}
+
+impl<K, V, S> SegmentedLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn with_maps(young: FixedSizeLruMap<K, V, S>, old: FixedSizeLruMap<K, V, S>) -> Self {
+        SegmentedLruMap {
+            demotions: Mutex::new(old.evictions()),
+            old,
+            promotions: Mutex::new(()),
+            young,
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.old.contains_key(key) || self.young.contains_key(key)
+    }
+
+    /// Returns the value of `key`, promoting it to the old map if it was young.
+    pub fn get(&self, key: &K) -> Option<MapGuard<V>> {
+        if let Some(guard) = self.old.get(key) {
+            return Some(guard);
+        }
+
+        let promotion = self.promotions.lock();
+
+        // another reader may have promoted the key while we were waiting.
+        let Some(young) = self.young.remove(key) else {
+            return self.old.get(key);
+        };
+
+        let guard = self
+            .old
+            .insert_arc(key.clone(), MapGuard::into_arc(young))
+            .0;
+        self.demote();
+        drop(promotion);
+        Some(guard)
+    }
+
+    pub fn get_or_init<F>(&self, key: K, f: F) -> MapGuard<V>
+    where
+        F: FnOnce() -> V,
+    {
+        match self.get(&key) {
+            Some(guard) => guard,
+            None => self.young.get_or_init(key, f),
+        }
+    }
+
+    /// Inserts a value in the young map, or replaces it in the old map.
+    pub fn insert(&self, key: K, value: V) -> (MapGuard<V>, Option<MapGuard<V>>) {
+        // a promotion in flight would otherwise move the previous value over this one.
+        let _promotion = self.promotions.lock();
+
+        if self.old.contains_key(&key) {
+            return self.old.insert(key, value);
+        }
+
+        self.young.insert(key, value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.old.is_empty() && self.young.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.old.len() + self.young.len()
+    }
+
+    /// Returns the map of the entries read at least twice.
+    pub fn old(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.old
+    }
+
+    pub fn remove(&self, key: &K) -> Option<MapGuard<V>> {
+        let _promotion = self.promotions.lock();
+        self.old.remove(key).or_else(|| self.young.remove(key))
+    }
+
+    /// Returns the map of the entries inserted but not read since.
+    pub fn young(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.young
+    }
+
+    /// Moves the entries evicted from the old map back to the young one, unless
+    /// the key was inserted again since. Called with the promotions locked.
+    fn demote(&self) {
+        let mut demotions = self.demotions.lock();
+
+        while let Some((key, guard)) = demotions.try_recv() {
+            if !self.contains_key(&key) {
+                self.young.insert_arc(key, MapGuard::into_arc(guard));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_segmented_lru_map() {
+    let map = SegmentedLruMap::with_capacity(2, 1);
+    map.insert(1, "a");
+    map.insert(2, "b");
+
+    assert_eq!("a", *map.get(&1).unwrap());
+    assert!(map.old().contains_key(&1));
+
+    // promoting 2 demotes 1 back to the young map.
+    assert_eq!("b", *map.get(&2).unwrap());
+    assert!(map.young().contains_key(&1));
+    assert!(map.old().contains_key(&2));
+
+    // replacing an old value keeps it old.
+    map.insert(2, "B");
+    assert_eq!("B", *map.old().get(&2).unwrap());
+
+    map.insert(3, "c");
+    map.insert(4, "d");
+    assert!(!map.contains_key(&1));
+    assert_eq!(3, map.len());
+}
+
+#[test]
+fn test_concurrent_promotion() {
+    use std::{sync::Barrier, thread::scope};
+
+    for _ in 0..100 {
+        let map = SegmentedLruMap::with_capacity(2, 2);
+        let barrier = Barrier::new(4);
+        map.insert(1, "a");
+
+        scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    barrier.wait();
+                    assert_eq!("a", *map.get(&1).unwrap());
+                });
+            }
+        });
+    }
+}
+
+#[test]
+fn test_insert_during_promotion() {
+    use std::{thread::scope, time::Duration};
+
+    let map = SegmentedLruMap::with_capacity(2, 2);
+    map.insert(1, "a");
+
+    scope(|s| {
+        // stop a promotion halfway, between the young and the old map.
+        let promotion = map.promotions.lock();
+        let young = map.young.remove(&1).unwrap();
+
+        s.spawn(|| map.insert(1, "b"));
+        std::thread::sleep(Duration::from_millis(20));
+
+        map.old.insert_arc(1, MapGuard::into_arc(young));
+        drop(promotion);
+    });
+
+    // the promotion must never move "a" over the newer value.
+    assert_eq!("b", *map.get(&1).unwrap());
+    assert_eq!(1, map.len());
+}
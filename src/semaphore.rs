@@ -0,0 +1,82 @@
+use parking_lot::{Condvar, Mutex};
+use std::{
+    future::poll_fn,
+    task::{Poll, Waker},
+};
+
+/// Limits how many loaders run at the same time, for both the blocking and the async paths.
+pub(crate) struct Semaphore {
+    available: Condvar,
+    state: Mutex<State>,
+}
+
+struct State {
+    permits: usize,
+    wakers: Vec<Waker>,
+}
+
+/// Gives its permit back when dropped.
+pub(crate) struct Permit<'a>(&'a Semaphore);
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        Semaphore {
+            available: Condvar::new(),
+            state: Mutex::new(State {
+                permits,
+                wakers: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn acquire(&self) -> Permit<'_> {
+        let mut state = self.state.lock();
+
+        while state.permits == 0 {
+            self.available.wait(&mut state);
+        }
+
+        state.permits -= 1;
+        Permit(self)
+    }
+
+    pub async fn acquire_async(&self) -> Permit<'_> {
+        poll_fn(|cx| {
+            let mut state = self.state.lock();
+
+            if state.permits == 0 {
+                state.wakers.push(cx.waker().clone());
+                return Poll::Pending;
+            }
+
+            state.permits -= 1;
+            Poll::Ready(Permit(self))
+        })
+        .await
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.0.state.lock();
+            state.permits += 1;
+            std::mem::take(&mut state.wakers)
+        };
+
+        // every waiter polls again, the ones losing the race register themselves back.
+        self.0.available.notify_one();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+#[test]
+fn test_semaphore() {
+    let semaphore = Semaphore::new(1);
+    let permit = semaphore.acquire();
+    assert_eq!(0, semaphore.state.lock().permits);
+
+    drop(permit);
+    let _permit = crate::future::block_on(semaphore.acquire_async());
+    assert_eq!(0, semaphore.state.lock().permits);
+}
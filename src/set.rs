@@ -0,0 +1,125 @@
+//! A fixed size set keeping only the most recently used keys.
+use crate::FixedSizeLruMap;
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    sync::Arc,
+};
+
+/// A set keeping up to its capacity of the most recently inserted or checked keys.
+///
+/// It is a [`FixedSizeLruMap`] without values, for deduplication or tracking
+/// what was seen recently.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::FixedSizeLruSet;
+///
+/// let seen = FixedSizeLruSet::with_capacity(2);
+/// assert!(seen.insert("a"));
+/// assert!(!seen.insert("a"));
+///
+/// seen.insert("b");
+/// seen.insert("c");
+/// assert!(!seen.contains("a"));
+/// ```
+pub struct FixedSizeLruSet<K, S = RandomState> {
+    map: FixedSizeLruMap<K, (), S>,
+}
+
+impl<K> FixedSizeLruSet<K>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_map(FixedSizeLruMap::with_capacity(capacity))
+    }
+}
+
+impl<K, S> FixedSizeLruSet<K, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Wraps a map configured through its builder.
+    pub fn with_map(map: FixedSizeLruMap<K, (), S>) -> Self {
+        FixedSizeLruSet { map }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Returns whether the set holds `key`, marking it as recently used.
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get(key).is_some()
+    }
+
+    /// Adds `key` to the set, evicting the least recently used key when full.
+    ///
+    /// Returns false if the key was already there, in which case it is only
+    /// marked as recently used.
+    pub fn insert(&self, key: K) -> bool {
+        let mut map = self.map.map.write();
+
+        if let Some(guard) = map.get(&key) {
+            self.map.update_guard_age(guard);
+            return false;
+        }
+
+        let mut notices = Vec::new();
+        self.map
+            .insert_locked(&mut map, key, Arc::new(()), &mut notices);
+
+        drop(map);
+        self.map.notify(notices);
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the keys, in no particular order.
+    pub fn keys(&self) -> Vec<K> {
+        self.map.view().keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the underlying map.
+    pub fn map(&self) -> &FixedSizeLruMap<K, (), S> {
+        &self.map
+    }
+
+    /// Removes `key`, returning whether it was in the set.
+    pub fn remove(&self, key: &K) -> bool {
+        self.map.remove(key).is_some()
+    }
+}
+
+#[test]
+fn test_lru_set() {
+    let set = FixedSizeLruSet::with_capacity(2);
+    assert!(set.insert(1));
+    assert!(set.insert(2));
+
+    // re-inserting 1 makes 2 the least recently used.
+    assert!(!set.insert(1));
+    assert!(set.insert(3));
+
+    let mut keys = set.keys();
+    keys.sort_unstable();
+    assert_eq!(vec![1, 3], keys);
+
+    assert!(set.remove(&1));
+    assert!(!set.remove(&1));
+    assert_eq!(1, set.len());
+}
@@ -0,0 +1,530 @@
+//! Replays recorded accesses against eviction policies and capacities, to
+//! size a cache or pick a policy without deploying each candidate.
+//!
+//! # Example
+//! ```
+//! use fixed_size_lru_map::{
+//!     simulate::{simulate, PolicyKind},
+//!     Access, AccessOp,
+//! };
+//! use std::time::Duration;
+//!
+//! let trace = [1, 2, 1, 3, 1, 2]
+//!     .iter()
+//!     .map(|&key_hash| Access { at: Duration::ZERO, key_hash, op: AccessOp::Miss })
+//!     .collect::<Vec<_>>();
+//!
+//! let results = simulate(&trace, &[1, 2], &[PolicyKind::Lru, PolicyKind::Lfu]);
+//! assert_eq!(4, results.len());
+//! ```
+use crate::{Access, AccessOp};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// An eviction policy that can be simulated.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PolicyKind {
+    /// Evicts the least recently used key, like [`FixedSizeLruMap`](crate::FixedSizeLruMap).
+    Lru,
+    /// Evicts the least frequently used key, the least recent one on ties.
+    Lfu,
+    /// An LRU only admitting a new key when it is estimated to be used more
+    /// often than the key it would evict.
+    TinyLfu,
+    /// The optimal offline policy, evicting the key used again the furthest in
+    /// the future. No real cache can know the future, this is the ceiling the
+    /// other policies can be measured against.
+    Belady,
+}
+
+/// The outcome of replaying a trace with a policy and a capacity.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SimulationResult {
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub policy: PolicyKind,
+}
+
+impl SimulationResult {
+    pub fn hit_ratio(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            n => self.hits as f64 / n as f64,
+        }
+    }
+}
+
+/// Replays `trace` for every combination of `capacities` and `policies`.
+///
+/// Every lookup, hit or miss in the trace, is replayed as a lookup loading
+/// the key on a miss. The insert recorded right after a miss of the same key
+/// is the load already replayed, so it is skipped.
+pub fn simulate(
+    trace: &[Access],
+    capacities: &[usize],
+    policies: &[PolicyKind],
+) -> Vec<SimulationResult> {
+    let mut out = Vec::with_capacity(capacities.len() * policies.len());
+
+    for &policy in policies {
+        for &capacity in capacities {
+            let mut cache: Box<dyn Policy> = match policy {
+                PolicyKind::Lru => Box::new(Lru::new(capacity)),
+                PolicyKind::Lfu => Box::new(Lfu::new(capacity)),
+                PolicyKind::TinyLfu => Box::new(TinyLfu::new(capacity)),
+                PolicyKind::Belady => Box::new(Belady::new(capacity, trace)),
+            };
+
+            let mut result = SimulationResult {
+                capacity,
+                hits: 0,
+                misses: 0,
+                policy,
+            };
+
+            let mut missed = None;
+
+            for (i, a) in trace.iter().enumerate() {
+                cache.seek(i);
+
+                let loaded = missed.take() == Some(a.key_hash);
+
+                match a.op {
+                    AccessOp::Miss => missed = Some(a.key_hash),
+                    AccessOp::Insert if loaded => continue,
+                    _ => {}
+                }
+
+                match a.op {
+                    AccessOp::Hit | AccessOp::Miss => {
+                        if cache.get(a.key_hash) {
+                            result.hits += 1;
+                        } else {
+                            result.misses += 1;
+                            cache.insert(a.key_hash);
+                        }
+                    }
+                    AccessOp::Insert => cache.insert(a.key_hash),
+                    AccessOp::Remove => cache.remove(a.key_hash),
+                }
+            }
+
+            out.push(result);
+        }
+    }
+
+    out
+}
+
+trait Policy {
+    /// Tells that the next calls replay the access at `index` in the trace.
+    fn seek(&mut self, _index: usize) {}
+
+    /// Looks `key` up, returning true on a hit.
+    fn get(&mut self, key: u64) -> bool;
+
+    fn insert(&mut self, key: u64);
+
+    fn remove(&mut self, key: u64);
+}
+
+struct Lru {
+    capacity: usize,
+    keys: HashMap<u64, u64>,
+    order: BTreeMap<u64, u64>,
+    tick: u64,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Lru {
+            capacity,
+            keys: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn oldest(&self) -> Option<u64> {
+        self.order.first_key_value().map(|(_, &k)| k)
+    }
+
+    fn touch(&mut self, key: u64) {
+        self.tick += 1;
+
+        if let Some(old) = self.keys.insert(key, self.tick) {
+            self.order.remove(&old);
+        }
+
+        self.order.insert(self.tick, key);
+    }
+}
+
+impl Policy for Lru {
+    fn get(&mut self, key: u64) -> bool {
+        let hit = self.keys.contains_key(&key);
+
+        if hit {
+            self.touch(key);
+        }
+
+        hit
+    }
+
+    fn insert(&mut self, key: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if !self.keys.contains_key(&key) && self.keys.len() == self.capacity {
+            if let Some(oldest) = self.oldest() {
+                self.remove(oldest);
+            }
+        }
+
+        self.touch(key);
+    }
+
+    fn remove(&mut self, key: u64) {
+        if let Some(tick) = self.keys.remove(&key) {
+            self.order.remove(&tick);
+        }
+    }
+}
+
+struct Lfu {
+    capacity: usize,
+    keys: HashMap<u64, (u64, u64)>,
+    order: BTreeSet<(u64, u64, u64)>,
+    tick: u64,
+}
+
+impl Lfu {
+    fn new(capacity: usize) -> Self {
+        Lfu {
+            capacity,
+            keys: HashMap::new(),
+            order: BTreeSet::new(),
+            tick: 0,
+        }
+    }
+
+    fn touch(&mut self, key: u64) {
+        self.tick += 1;
+
+        let freq = match self.keys.get(&key) {
+            Some(&(freq, tick)) => {
+                self.order.remove(&(freq, tick, key));
+                freq + 1
+            }
+            None => 1,
+        };
+
+        self.keys.insert(key, (freq, self.tick));
+        self.order.insert((freq, self.tick, key));
+    }
+}
+
+impl Policy for Lfu {
+    fn get(&mut self, key: u64) -> bool {
+        let hit = self.keys.contains_key(&key);
+
+        if hit {
+            self.touch(key);
+        }
+
+        hit
+    }
+
+    fn insert(&mut self, key: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if !self.keys.contains_key(&key) && self.keys.len() == self.capacity {
+            if let Some(&(_, _, victim)) = self.order.first() {
+                self.remove(victim);
+            }
+        }
+
+        self.touch(key);
+    }
+
+    fn remove(&mut self, key: u64) {
+        if let Some((freq, tick)) = self.keys.remove(&key) {
+            self.order.remove(&(freq, tick, key));
+        }
+    }
+}
+
+struct TinyLfu {
+    lru: Lru,
+    sketch: Sketch,
+}
+
+impl TinyLfu {
+    fn new(capacity: usize) -> Self {
+        TinyLfu {
+            lru: Lru::new(capacity),
+            sketch: Sketch::new(capacity),
+        }
+    }
+}
+
+impl Policy for TinyLfu {
+    fn get(&mut self, key: u64) -> bool {
+        self.sketch.increment(key);
+        self.lru.get(key)
+    }
+
+    fn insert(&mut self, key: u64) {
+        let full = self.lru.keys.len() == self.lru.capacity;
+
+        if full && !self.lru.keys.contains_key(&key) {
+            match self.lru.oldest() {
+                Some(victim) if self.sketch.estimate(key) > self.sketch.estimate(victim) => {
+                    self.lru.remove(victim)
+                }
+                _ => return,
+            }
+        }
+
+        self.lru.insert(key);
+    }
+
+    fn remove(&mut self, key: u64) {
+        self.lru.remove(key);
+    }
+}
+
+struct Belady {
+    capacity: usize,
+    /// The index of the next lookup of each cached key.
+    keys: HashMap<u64, usize>,
+    /// For each access of the trace, the index of the next lookup of its key.
+    next: Vec<usize>,
+    order: BTreeSet<(usize, u64)>,
+    pos: usize,
+}
+
+impl Belady {
+    fn new(capacity: usize, trace: &[Access]) -> Self {
+        let mut next = vec![usize::MAX; trace.len()];
+        let mut seen = HashMap::new();
+
+        for (i, a) in trace.iter().enumerate().rev() {
+            next[i] = seen.get(&a.key_hash).copied().unwrap_or(usize::MAX);
+
+            if matches!(a.op, AccessOp::Hit | AccessOp::Miss) {
+                seen.insert(a.key_hash, i);
+            }
+        }
+
+        Belady {
+            capacity,
+            keys: HashMap::new(),
+            next,
+            order: BTreeSet::new(),
+            pos: 0,
+        }
+    }
+
+    fn next_use(&self) -> usize {
+        self.next.get(self.pos).copied().unwrap_or(usize::MAX)
+    }
+
+    fn touch(&mut self, key: u64) {
+        let next = self.next_use();
+
+        if let Some(old) = self.keys.insert(key, next) {
+            self.order.remove(&(old, key));
+        }
+
+        self.order.insert((next, key));
+    }
+}
+
+impl Policy for Belady {
+    fn seek(&mut self, index: usize) {
+        self.pos = index;
+    }
+
+    fn get(&mut self, key: u64) -> bool {
+        let hit = self.keys.contains_key(&key);
+
+        if hit {
+            self.touch(key);
+        }
+
+        hit
+    }
+
+    fn insert(&mut self, key: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if !self.keys.contains_key(&key) && self.keys.len() == self.capacity {
+            match self.order.last() {
+                // the new key is needed later than every cached one, skip it.
+                Some(&(furthest, _)) if furthest <= self.next_use() => return,
+                Some(&(_, victim)) => self.remove(victim),
+                None => {}
+            }
+        }
+
+        self.touch(key);
+    }
+
+    fn remove(&mut self, key: u64) {
+        if let Some(next) = self.keys.remove(&key) {
+            self.order.remove(&(next, key));
+        }
+    }
+}
+
+/// A count-min sketch of the key frequencies, halved periodically so old
+/// popularity fades away.
+struct Sketch {
+    additions: usize,
+    counters: Vec<[u8; 4]>,
+    sample: usize,
+}
+
+const SEEDS: [u64; 4] = [
+    0x9E37_79B9_7F4A_7C15,
+    0xC2B2_AE3D_27D4_EB4F,
+    0x1656_67B1_9E37_79F9,
+    0x85EB_CA77_C2B2_AE63,
+];
+
+impl Sketch {
+    fn new(capacity: usize) -> Self {
+        Sketch {
+            additions: 0,
+            counters: vec![[0; 4]; capacity.max(1).next_power_of_two() * 4],
+            sample: capacity.max(1) * 10,
+        }
+    }
+
+    fn index(&self, key: u64, row: usize) -> usize {
+        (key.wrapping_mul(SEEDS[row]) >> 32) as usize & (self.counters.len() - 1)
+    }
+
+    fn estimate(&self, key: u64) -> u8 {
+        (0..4)
+            .map(|row| self.counters[self.index(key, row)][row])
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn increment(&mut self, key: u64) {
+        for row in 0..4 {
+            let i = self.index(key, row);
+            self.counters[i][row] = self.counters[i][row].saturating_add(1);
+        }
+
+        self.additions += 1;
+
+        if self.additions == self.sample {
+            self.additions = 0;
+            self.counters.iter_mut().flatten().for_each(|c| *c /= 2);
+        }
+    }
+}
+
+#[test]
+fn test_simulate() {
+    use std::time::Duration;
+
+    // a hot key mixed with a scan of keys never seen again.
+    let trace = (0..1000u64)
+        .flat_map(|i| [0, 1000 + i])
+        .map(|key_hash| Access {
+            at: Duration::ZERO,
+            key_hash,
+            op: AccessOp::Miss,
+        })
+        .collect::<Vec<_>>();
+
+    let results = simulate(
+        &trace,
+        &[1, 2],
+        &[
+            PolicyKind::Lru,
+            PolicyKind::Lfu,
+            PolicyKind::TinyLfu,
+            PolicyKind::Belady,
+        ],
+    );
+
+    let ratio = |policy, capacity| {
+        results
+            .iter()
+            .find(|r| r.policy == policy && r.capacity == capacity)
+            .unwrap()
+            .hit_ratio()
+    };
+
+    // the scan flushes the hot key out of a single slot, unless new keys are
+    // only admitted when they are used more.
+    assert_eq!(0.0, ratio(PolicyKind::Lru, 1));
+    assert!(ratio(PolicyKind::Lru, 2) > 0.49);
+    assert!(ratio(PolicyKind::Lfu, 2) > 0.49);
+    assert!(ratio(PolicyKind::TinyLfu, 1) > 0.49);
+
+    // nothing beats knowing the future.
+    for policy in [PolicyKind::Lru, PolicyKind::Lfu, PolicyKind::TinyLfu] {
+        for capacity in [1, 2] {
+            assert!(ratio(PolicyKind::Belady, capacity) >= ratio(policy, capacity));
+        }
+    }
+
+    assert!(ratio(PolicyKind::Belady, 1) > 0.49);
+}
+
+#[test]
+fn test_simulate_loads() {
+    use std::time::Duration;
+    use AccessOp::{Hit, Insert, Miss};
+
+    let access = |key_hash, op| Access {
+        at: Duration::ZERO,
+        key_hash,
+        op,
+    };
+
+    // the loads following the misses change nothing.
+    let lookups = [
+        (1, Miss),
+        (4, Miss),
+        (3, Hit),
+        (2, Hit),
+        (1, Hit),
+        (3, Hit),
+        (4, Hit),
+        (3, Miss),
+        (3, Hit),
+    ]
+    .map(|(k, op)| access(k, op))
+    .to_vec();
+    let loads = lookups
+        .iter()
+        .flat_map(|a| match a.op {
+            Miss => vec![*a, access(a.key_hash, Insert)],
+            _ => vec![*a],
+        })
+        .collect::<Vec<_>>();
+
+    let policies = [
+        PolicyKind::Lru,
+        PolicyKind::Lfu,
+        PolicyKind::TinyLfu,
+        PolicyKind::Belady,
+    ];
+
+    assert_eq!(
+        simulate(&lookups, &[1, 2, 3], &policies),
+        simulate(&loads, &[1, 2, 3], &policies)
+    );
+}
@@ -0,0 +1,250 @@
+//! A storage mode keeping the values inline in a slab instead of behind an `Arc`.
+use crate::{age, lock::Lock};
+use std::{
+    collections::hash_map::{HashMap, RandomState},
+    hash::{BuildHasher, Hash},
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+};
+
+/// A generation checked reference to a slot of a [`SlabLruMap`].
+///
+/// A handle stops resolving once its entry has been removed or evicted, even if
+/// the slot is reused by another entry.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SlabHandle {
+    generation: u64,
+    index: usize,
+}
+
+/// A fixed size lru map storing its values in a slab.
+///
+/// Unlike [`FixedSizeLruMap`](crate::FixedSizeLruMap), there is no per entry
+/// allocation nor reference counting. Values never escape the map: they are
+/// read in place by [`get_with`](Self::get_with), copied out by [`get`](Self::get)
+/// and moved out when removed or evicted. No lock outlives a call.
+pub struct SlabLruMap<K, V, S = RandomState> {
+    age: AtomicU64,
+    capacity: usize,
+    inner: Lock<Slab<K, V, S>>,
+}
+
+struct Slab<K, V, S> {
+    free: Vec<usize>,
+    keys: HashMap<K, usize, S>,
+    slots: Vec<Slot<V>>,
+}
+
+struct Slot<V> {
+    age: AtomicU64,
+    generation: u64,
+    value: Option<V>,
+}
+
+impl<K, V> SlabLruMap<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K, V, S> SlabLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        SlabLruMap {
+            age: AtomicU64::new(0),
+            capacity,
+            inner: Lock::new(Slab {
+                free: Vec::new(),
+                keys: HashMap::with_capacity_and_hasher(capacity, hash_builder),
+                slots: Vec::with_capacity(capacity),
+            }),
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.read().keys.contains_key(key)
+    }
+
+    /// Returns a copy of the value of `key`.
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.get_with(key, V::clone)
+    }
+
+    /// Calls `f` with the value of `key` without keeping the map locked afterward.
+    pub fn get_with<F, R>(&self, key: &K, f: F) -> Option<R>
+    where
+        F: FnOnce(&V) -> R,
+    {
+        let slab = self.inner.read();
+        let index = *slab.keys.get(key)?;
+        Some(f(self.touch(&slab, index)))
+    }
+
+    /// Returns a copy of the value of `handle`.
+    pub fn get_handle(&self, handle: SlabHandle) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.get_handle_with(handle, V::clone)
+    }
+
+    /// Calls `f` with the value of `handle` without keeping the map locked afterward.
+    pub fn get_handle_with<F, R>(&self, handle: SlabHandle, f: F) -> Option<R>
+    where
+        F: FnOnce(&V) -> R,
+    {
+        let slab = self.inner.read();
+        let slot = slab.slots.get(handle.index)?;
+
+        if slot.generation != handle.generation || slot.value.is_none() {
+            return None;
+        }
+
+        Some(f(self.touch(&slab, handle.index)))
+    }
+
+    pub fn handle(&self, key: &K) -> Option<SlabHandle> {
+        let slab = self.inner.read();
+        let index = *slab.keys.get(key)?;
+
+        Some(SlabHandle {
+            generation: slab.slots[index].generation,
+            index,
+        })
+    }
+
+    /// Inserts a value, returning the value it replaced or the one evicted to make room.
+    pub fn insert(&self, key: K, value: V) -> (SlabHandle, Option<V>) {
+        let mut slab = self.inner.write();
+        let mut age = self.age.fetch_add(1, Relaxed);
+
+        if age >= age::REBASE_THRESHOLD {
+            let slab = &*slab;
+            age::rebase(&self.age, slab.keys.values().map(|&i| &slab.slots[i].age));
+            age = self.age.fetch_add(1, Relaxed);
+        }
+
+        if let Some(&index) = slab.keys.get(&key) {
+            let slot = &mut slab.slots[index];
+            *slot.age.get_mut() = age;
+
+            let handle = SlabHandle {
+                generation: slot.generation,
+                index,
+            };
+
+            return (handle, slot.value.replace(value));
+        }
+
+        let mut old = None;
+
+        if slab.keys.len() >= self.capacity {
+            let Some(oldest) = slab
+                .keys
+                .iter()
+                .min_by_key(|(_, &i)| slab.slots[i].age.load(Relaxed))
+                .map(|(k, _)| k.clone())
+            else {
+                // a zero capacity map keeps nothing.
+                return (
+                    SlabHandle {
+                        generation: u64::MAX,
+                        index: usize::MAX,
+                    },
+                    Some(value),
+                );
+            };
+
+            old = slab.remove(&oldest);
+        }
+
+        let index = match slab.free.pop() {
+            Some(index) => index,
+            None => {
+                slab.slots.push(Slot {
+                    age: AtomicU64::new(0),
+                    generation: 0,
+                    value: None,
+                });
+                slab.slots.len() - 1
+            }
+        };
+
+        let slot = &mut slab.slots[index];
+        *slot.age.get_mut() = age;
+        slot.value = Some(value);
+
+        let handle = SlabHandle {
+            generation: slot.generation,
+            index,
+        };
+
+        slab.keys.insert(key, index);
+        (handle, old)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().keys.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().keys.len()
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.inner.write().remove(key)
+    }
+
+    /// Marks the slot at `index` as the most recently used, returning its value.
+    fn touch<'a>(&self, slab: &'a Slab<K, V, S>, index: usize) -> &'a V {
+        let slot = &slab.slots[index];
+        slot.age.store(self.age.fetch_add(1, Relaxed), Relaxed);
+        slot.value.as_ref().expect("occupied slot")
+    }
+}
+
+impl<K, V, S> Slab<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.keys.remove(key)?;
+        let slot = &mut self.slots[index];
+
+        slot.generation += 1;
+        self.free.push(index);
+        slot.value.take()
+    }
+}
+
+#[test]
+fn test_slab_handles() {
+    let map = SlabLruMap::with_capacity(2);
+    let (a, _) = map.insert("a", 1);
+    map.insert("b", 2);
+
+    assert_eq!(Some(1), map.get(&"a"));
+
+    // "b" is the least recently used.
+    let (c, evicted) = map.insert("c", 3);
+    assert_eq!(Some(2), evicted);
+    assert_eq!(Some(1), map.get_handle(a));
+    assert_eq!(Some(6), map.get_handle_with(c, |v| v * 2));
+
+    assert_eq!(Some(1), map.remove(&"a"));
+    assert!(map.get_handle(a).is_none());
+
+    // the slot of "a" is reused but its old handle stays invalid.
+    let (d, _) = map.insert("d", 4);
+    assert!(map.get_handle(a).is_none());
+    assert_eq!(Some(4), map.get_handle(d));
+}
@@ -0,0 +1,224 @@
+//! Checkpointing of the content of a map.
+use crate::{FixedSizeLruMap, MapGuard};
+use std::hash::{BuildHasher, Hash};
+
+/// The content of a map, captured by [`snapshot`](FixedSizeLruMap::snapshot)
+/// to warm up another one with [`restore`](FixedSizeLruMap::restore).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheSnapshot<K, V> {
+    pub capacity: usize,
+    /// The entries from the least to the most recently used.
+    pub entries: Vec<(K, V)>,
+}
+
+impl<K, V> FixedSizeLruMap<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    /// Creates a map of the capacity recorded in `snapshot`, holding its entries
+    /// in the same recency order.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(2);
+    /// map.insert("a", 1);
+    ///
+    /// let restored = FixedSizeLruMap::from_snapshot(map.snapshot());
+    /// assert_eq!(2, restored.capacity());
+    /// assert_eq!(Some(1), restored.get(&"a").map(|v| *v));
+    /// ```
+    pub fn from_snapshot(snapshot: CacheSnapshot<K, V>) -> Self {
+        let map = Self::with_capacity(snapshot.capacity);
+        map.restore(snapshot);
+        map
+    }
+}
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Returns the entries from the least to the most recently used.
+    ///
+    /// Inserting them in this order in a map of the same [`capacity`](Self::capacity),
+    /// e.g. with [`extend`](Extend::extend), rebuilds the same map.
+    pub fn entries_by_recency(&self) -> Vec<(K, MapGuard<V>)>
+    where
+        K: Clone,
+    {
+        let mut entries = self.entries();
+        entries.sort_unstable_by_key(|(_, v)| v.age());
+        entries
+    }
+
+    /// Returns the keys from the least to the most recently used, a lightweight
+    /// alternative to a [`snapshot`](Self::snapshot) when the values can be loaded again.
+    pub fn export_keys_by_recency(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.entries_by_recency()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect()
+    }
+
+    /// Loads the values of `keys`, given from the least to the most recently
+    /// used, skipping the keys already present and the ones `loader` returns
+    /// `None` for. Returns the number of inserted values.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// map.insert(1, 10);
+    /// map.insert(2, 20);
+    ///
+    /// let keys = map.export_keys_by_recency();
+    /// let warm = FixedSizeLruMap::with_capacity(10);
+    /// assert_eq!(2, warm.import_warm_keys(keys, |k| Some(k * 10)));
+    /// ```
+    pub fn import_warm_keys<I, F>(&self, keys: I, mut loader: F) -> usize
+    where
+        F: FnMut(&K) -> Option<V>,
+        I: IntoIterator<Item = K>,
+        K: Clone,
+    {
+        let mut inserted = 0;
+
+        for k in keys {
+            if self.contains_key(&k) {
+                continue;
+            }
+
+            if let Some(v) = self.load(&k, || loader(&k)) {
+                self.insert(k, v);
+                inserted += 1;
+            }
+        }
+
+        inserted
+    }
+
+    /// Inserts the entries of `snapshot`, keeping their recency order.
+    ///
+    /// The entries are expected from the least to the most recently used, as
+    /// [`snapshot`](Self::snapshot) returns them. When the map has a smaller
+    /// [`capacity`](Self::capacity) than the snapshot, only its most recently
+    /// used entries are restored, the others are skipped without being inserted.
+    /// [`from_snapshot`](Self::from_snapshot) creates a map of the capacity of the snapshot.
+    pub fn restore(&self, snapshot: CacheSnapshot<K, V>)
+    where
+        K: Clone,
+    {
+        let skipped = snapshot.entries.len().saturating_sub(self.capacity);
+
+        for (k, v) in snapshot.entries.into_iter().skip(skipped) {
+            self.insert(k, v);
+        }
+    }
+
+    /// Captures the values and the recency order of the entries, e.g. to warm up
+    /// the cache of the next run of a service.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(2);
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let restored = FixedSizeLruMap::with_capacity(2);
+    /// restored.restore(map.snapshot());
+    /// assert_eq!(Some(2), restored.get(&"b").map(|v| *v));
+    /// ```
+    pub fn snapshot(&self) -> CacheSnapshot<K, V>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        CacheSnapshot {
+            capacity: self.capacity,
+            entries: self
+                .entries_by_recency()
+                .into_iter()
+                .map(|(k, v)| (k, V::clone(&v)))
+                .collect(),
+        }
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Inserts the entries in order, the last one becoming the most recently used.
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+#[test]
+fn test_entries_by_recency() {
+    let map = FixedSizeLruMap::with_capacity(3);
+    map.insert(1, 'a');
+    map.insert(2, 'b');
+    map.insert(3, 'c');
+    map.get(&1);
+
+    let entries = map.entries_by_recency();
+    assert_eq!(
+        vec![2, 3, 1],
+        entries.iter().map(|(k, _)| *k).collect::<Vec<_>>()
+    );
+
+    let mut copy = FixedSizeLruMap::with_capacity(map.capacity());
+    copy.extend(entries.into_iter().map(|(k, v)| (k, *v)));
+    copy.insert(4, 'd');
+    assert!(!copy.contains_key(&2));
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let map = FixedSizeLruMap::with_capacity(3);
+    map.insert(1, 'a');
+    map.insert(2, 'b');
+    map.insert(3, 'c');
+    map.get(&1);
+
+    let snapshot = map.snapshot();
+    assert_eq!(vec![(2, 'b'), (3, 'c'), (1, 'a')], snapshot.entries);
+
+    let smaller = FixedSizeLruMap::with_capacity(2);
+    let mut evictions = smaller.evictions();
+    smaller.restore(snapshot);
+    assert!(evictions.try_recv().is_none());
+    assert_eq!(
+        vec![3, 1],
+        smaller
+            .entries_by_recency()
+            .iter()
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_from_snapshot() {
+    let map = FixedSizeLruMap::with_capacity(3);
+    map.insert(1, 'a');
+    map.insert(2, 'b');
+    map.get(&1);
+
+    let restored = FixedSizeLruMap::from_snapshot(map.snapshot());
+    assert_eq!(3, restored.capacity());
+    assert_eq!(map.snapshot(), restored.snapshot());
+}
@@ -0,0 +1,249 @@
+//! A cache spilling its evicted entries to a file on disk.
+use crate::{persist::Persist, EventReceiver, FixedSizeLruMap, MapGuard};
+use parking_lot::Mutex;
+use std::{
+    collections::hash_map::{HashMap, RandomState},
+    fs::{self, File, OpenOptions},
+    hash::{BuildHasher, Hash},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// The file is only compacted past this size, below it the dead bytes are cheap.
+const COMPACT_MIN_LEN: u64 = 1 << 20;
+
+/// A cache keeping its most recently used entries in memory and the entries
+/// evicted for capacity in a file, read back transparently on a miss.
+///
+/// The memory use is bounded by the capacity of the map, the file grows with
+/// the number of spilled entries. It is rewritten once more than half of it is
+/// taken by entries read back or removed. The file is a scratch space, it is
+/// emptied when the cache is opened.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::SpillCache;
+///
+/// let path = std::env::temp_dir().join("fixed_size_lru_map_doc.spill");
+/// let cache = SpillCache::open(&path, 1).unwrap();
+/// cache.insert(1u32, "a".to_owned()).unwrap();
+/// cache.insert(2, "b".to_owned()).unwrap();
+///
+/// assert_eq!(1, cache.disk_len());
+/// assert_eq!("a", *cache.get(&1).unwrap().unwrap());
+/// # std::fs::remove_file(path).unwrap();
+/// ```
+pub struct SpillCache<K, V, S = RandomState> {
+    disk: Mutex<Disk<K>>,
+    evictions: Mutex<EventReceiver<(K, MapGuard<V>)>>,
+    map: FixedSizeLruMap<K, V, S>,
+}
+
+struct Disk<K> {
+    file: File,
+    index: HashMap<K, (u64, u64)>,
+    len: u64,
+    live: u64,
+    path: PathBuf,
+}
+
+impl<K, V> SpillCache<K, V>
+where
+    K: Clone + Eq + Hash + Persist,
+    V: Persist,
+{
+    /// Keeps up to `capacity` entries in memory, spilling the others to `path`.
+    pub fn open<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        Self::with_map(path, FixedSizeLruMap::with_capacity(capacity))
+    }
+}
+
+impl<K, V, S> SpillCache<K, V, S>
+where
+    K: Clone + Eq + Hash + Persist,
+    V: Persist,
+    S: BuildHasher,
+{
+    /// Spills the entries evicted from `map` to `path`.
+    pub fn with_map<P: AsRef<Path>>(path: P, map: FixedSizeLruMap<K, V, S>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        Ok(SpillCache {
+            disk: Mutex::new(Disk {
+                file: create(&path)?,
+                index: HashMap::new(),
+                len: 0,
+                live: 0,
+                path,
+            }),
+            evictions: Mutex::new(map.evictions()),
+            map,
+        })
+    }
+
+    /// Returns the number of entries in the file.
+    pub fn disk_len(&self) -> usize {
+        self.disk.lock().index.len()
+    }
+
+    /// Returns the value of `key`, reading it back from the file if it was spilled.
+    pub fn get(&self, key: &K) -> io::Result<Option<MapGuard<V>>> {
+        if let Some(v) = self.map.get(key) {
+            return Ok(Some(v));
+        }
+
+        let mut disk = self.disk.lock();
+
+        // promoted by someone else while waiting for the lock.
+        if let Some(v) = self.map.get(key) {
+            return Ok(Some(v));
+        }
+
+        let Some(value) = disk.take(key)? else {
+            return Ok(None);
+        };
+
+        let guard = self.map.insert(key.clone(), value).0;
+        self.spill(&mut disk)?;
+        Ok(Some(guard))
+    }
+
+    pub fn insert(&self, key: K, value: V) -> io::Result<MapGuard<V>> {
+        let mut disk = self.disk.lock();
+        disk.forget(&key);
+
+        let guard = self.map.insert(key, value).0;
+        self.spill(&mut disk)?;
+        Ok(guard)
+    }
+
+    /// Returns the underlying map, holding the entries kept in memory.
+    pub fn map(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.map
+    }
+
+    /// Removes `key` from both the memory and the file, returning whether it was found.
+    pub fn remove(&self, key: &K) -> bool {
+        let mut disk = self.disk.lock();
+        self.map.remove(key).is_some() | disk.forget(key)
+    }
+
+    fn spill(&self, disk: &mut Disk<K>) -> io::Result<()> {
+        let mut evictions = self.evictions.lock();
+
+        while let Some((k, v)) = evictions.try_recv() {
+            disk.put(k, &*v)?;
+        }
+
+        if disk.len > COMPACT_MIN_LEN && disk.len > 2 * disk.live {
+            disk.compact()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<K> Disk<K>
+where
+    K: Eq + Hash,
+{
+    fn compact(&mut self) -> io::Result<()> {
+        let tmp = self.path.with_extension("compact");
+        let mut file = create(&tmp)?;
+        let mut len = 0;
+
+        for (offset, size) in self.index.values_mut() {
+            let bytes = read(&mut self.file, *offset, *size)?;
+            file.write_all(&bytes)?;
+            *offset = len;
+            len += *size;
+        }
+
+        fs::rename(&tmp, &self.path)?;
+        self.file = file;
+        self.len = len;
+        Ok(())
+    }
+
+    fn forget(&mut self, key: &K) -> bool {
+        let Some((_, size)) = self.index.remove(key) else {
+            return false;
+        };
+
+        self.live -= size;
+        true
+    }
+
+    fn put<V: Persist>(&mut self, key: K, value: &V) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        value.write_to(&mut bytes)?;
+
+        let size = bytes.len() as u64;
+        self.file.seek(SeekFrom::Start(self.len))?;
+        self.file.write_all(&bytes)?;
+
+        if let Some((_, old)) = self.index.insert(key, (self.len, size)) {
+            self.live -= old;
+        }
+
+        self.len += size;
+        self.live += size;
+        Ok(())
+    }
+
+    fn take<V: Persist>(&mut self, key: &K) -> io::Result<Option<V>> {
+        let Some(&(offset, size)) = self.index.get(key) else {
+            return Ok(None);
+        };
+
+        let bytes = read(&mut self.file, offset, size)?;
+        self.forget(key);
+        V::read_from(&mut &bytes[..]).map(Some)
+    }
+}
+
+fn create(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .read(true)
+        .write(true)
+        .open(path)
+}
+
+fn read(file: &mut File, offset: u64, size: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; size as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[test]
+fn test_spill() {
+    let path =
+        std::env::temp_dir().join(format!("fixed_size_lru_map_{}.spill", std::process::id()));
+    let cache = SpillCache::open(&path, 2).unwrap();
+
+    for i in 0..10u32 {
+        cache.insert(i, i.to_string()).unwrap();
+    }
+
+    assert_eq!((2, 8), (cache.map().len(), cache.disk_len()));
+
+    // reading back a spilled entry spills the least recently used one.
+    assert_eq!("3", *cache.get(&3).unwrap().unwrap());
+    assert_eq!((2, 8), (cache.map().len(), cache.disk_len()));
+    assert!(cache.map().contains_key(&3));
+
+    assert!(cache.remove(&0));
+    assert!(!cache.remove(&0));
+    assert!(cache.get(&0).unwrap().is_none());
+
+    cache.disk.lock().compact().unwrap();
+
+    for i in 1..10 {
+        assert_eq!(i.to_string(), *cache.get(&i).unwrap().unwrap());
+    }
+
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,254 @@
+use crate::RemovalCause;
+#[cfg(feature = "stats")]
+use std::{
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+    time::{Duration, Instant},
+};
+
+/// Number of buckets dividing the hit ratio window.
+#[cfg(feature = "stats")]
+const BUCKETS: usize = 10;
+
+/// The usage counters of a map.
+///
+/// Without the `stats` feature, this is empty and recording is free.
+#[derive(Default)]
+pub(crate) struct StatsCounters {
+    #[cfg(feature = "stats")]
+    evictions: AtomicU64,
+    #[cfg(feature = "stats")]
+    hits: AtomicU64,
+    #[cfg(feature = "stats")]
+    insertions: AtomicU64,
+    #[cfg(feature = "stats")]
+    misses: AtomicU64,
+    #[cfg(feature = "stats")]
+    removals: AtomicU64,
+    #[cfg(feature = "stats")]
+    replacements: AtomicU64,
+    #[cfg(feature = "stats")]
+    window: Window,
+}
+
+macro_rules! counter {
+    ($name:ident, $field:ident) => {
+        #[inline]
+        pub fn $name(&self) {
+            #[cfg(feature = "stats")]
+            self.$field.fetch_add(1, Relaxed);
+        }
+    };
+}
+
+impl StatsCounters {
+    counter!(insertion, insertions);
+
+    #[inline]
+    pub fn removal(&self, _cause: RemovalCause) {
+        #[cfg(feature = "stats")]
+        match _cause {
+            RemovalCause::Capacity => &self.evictions,
+            RemovalCause::Explicit => &self.removals,
+            RemovalCause::Replaced => &self.replacements,
+        }
+        .fetch_add(1, Relaxed);
+    }
+
+    #[inline]
+    pub fn hit(&self) {
+        #[cfg(feature = "stats")]
+        {
+            self.hits.fetch_add(1, Relaxed);
+            self.window.record(true);
+        }
+    }
+
+    #[inline]
+    pub fn miss(&self) {
+        #[cfg(feature = "stats")]
+        {
+            self.misses.fetch_add(1, Relaxed);
+            self.window.record(false);
+        }
+    }
+
+    /// Sets every counter back to zero, returning their previous values.
+    #[cfg(feature = "stats")]
+    pub fn reset(&self) -> CacheStats {
+        CacheStats {
+            evictions: self.evictions.swap(0, Relaxed),
+            hits: self.hits.swap(0, Relaxed),
+            insertions: self.insertions.swap(0, Relaxed),
+            misses: self.misses.swap(0, Relaxed),
+            removals: self.removals.swap(0, Relaxed),
+            replacements: self.replacements.swap(0, Relaxed),
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn set_window(&mut self, window: Duration) {
+        self.window = Window::new(window);
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn windowed_hit_ratio(&self) -> f64 {
+        self.window.hit_ratio()
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            evictions: self.evictions.load(Relaxed),
+            hits: self.hits.load(Relaxed),
+            insertions: self.insertions.load(Relaxed),
+            misses: self.misses.load(Relaxed),
+            removals: self.removals.load(Relaxed),
+            replacements: self.replacements.load(Relaxed),
+        }
+    }
+}
+
+/// The hits and misses of the recent past, in buckets rotating with time.
+#[cfg(feature = "stats")]
+struct Window {
+    bucket_nanos: u64,
+    buckets: [Bucket; BUCKETS],
+    start: Instant,
+}
+
+#[cfg(feature = "stats")]
+#[derive(Default)]
+struct Bucket {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    slot: AtomicU64,
+}
+
+#[cfg(feature = "stats")]
+impl Default for Window {
+    fn default() -> Self {
+        Window::new(Duration::from_secs(60))
+    }
+}
+
+#[cfg(feature = "stats")]
+impl Window {
+    fn new(window: Duration) -> Self {
+        let nanos = u64::try_from(window.as_nanos()).unwrap_or(u64::MAX);
+
+        Window {
+            bucket_nanos: (nanos / BUCKETS as u64).max(1),
+            buckets: Default::default(),
+            start: Instant::now(),
+        }
+    }
+
+    fn current_slot(&self) -> u64 {
+        let nanos = u64::try_from(self.start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        nanos / self.bucket_nanos
+    }
+
+    fn record(&self, hit: bool) {
+        let slot = self.current_slot();
+        let bucket = &self.buckets[(slot % BUCKETS as u64) as usize];
+        let old = bucket.slot.load(Relaxed);
+
+        // the first one to enter a new slot recycles the bucket; a few
+        // concurrent records may be lost, which is fine for a ratio.
+        if old != slot
+            && bucket
+                .slot
+                .compare_exchange(old, slot, Relaxed, Relaxed)
+                .is_ok()
+        {
+            bucket.hits.store(0, Relaxed);
+            bucket.misses.store(0, Relaxed);
+        }
+
+        match hit {
+            true => bucket.hits.fetch_add(1, Relaxed),
+            false => bucket.misses.fetch_add(1, Relaxed),
+        };
+    }
+
+    fn hit_ratio(&self) -> f64 {
+        let slot = self.current_slot();
+        let (hits, misses) = self
+            .buckets
+            .iter()
+            .filter(|b| slot - b.slot.load(Relaxed).min(slot) < BUCKETS as u64)
+            .fold((0, 0), |(h, m), b| {
+                (h + b.hits.load(Relaxed), m + b.misses.load(Relaxed))
+            });
+
+        match hits + misses {
+            0 => 0.0,
+            n => hits as f64 / n as f64,
+        }
+    }
+}
+
+/// Counters describing how a map has been used.
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Number of entries evicted to make room for new ones.
+    pub evictions: u64,
+    /// Number of lookups that found a value.
+    pub hits: u64,
+    /// Number of inserted values, including replacements.
+    pub insertions: u64,
+    /// Number of lookups that found nothing.
+    pub misses: u64,
+    /// Number of entries explicitly removed.
+    pub removals: u64,
+    /// Number of values replaced by the insertion of a new one.
+    pub replacements: u64,
+}
+
+#[cfg(feature = "stats")]
+impl CacheStats {
+    /// Returns the ratio of the lookups that found a value, or 0 without any lookup.
+    pub fn hit_ratio(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            n => self.hits as f64 / n as f64,
+        }
+    }
+
+    /// Returns what has been counted since `earlier` was taken.
+    ///
+    /// A counter reset in between restarts from zero instead of going negative.
+    pub fn delta_since(&self, earlier: &CacheStats) -> CacheStats {
+        CacheStats {
+            evictions: self.evictions.saturating_sub(earlier.evictions),
+            hits: self.hits.saturating_sub(earlier.hits),
+            insertions: self.insertions.saturating_sub(earlier.insertions),
+            misses: self.misses.saturating_sub(earlier.misses),
+            removals: self.removals.saturating_sub(earlier.removals),
+            replacements: self.replacements.saturating_sub(earlier.replacements),
+        }
+    }
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_windowed_hit_ratio() {
+    use std::thread::sleep;
+
+    let mut stats = StatsCounters::default();
+    stats.set_window(Duration::from_millis(50));
+
+    for _ in 0..10 {
+        stats.miss();
+    }
+
+    sleep(Duration::from_millis(60));
+    stats.hit();
+    stats.hit();
+    stats.miss();
+
+    // the startup misses have left the window.
+    assert!((stats.windowed_hit_ratio() - 2.0 / 3.0).abs() < f64::EPSILON);
+    assert!(stats.snapshot().hit_ratio() < 0.2);
+}
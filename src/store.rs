@@ -0,0 +1,393 @@
+//! Write-through caching propagating the mutations to a [`WriteStore`].
+#[cfg(feature = "std")]
+use crate::{maintenance::spawn_periodic, MaintenanceHandle};
+use crate::{FixedSizeLruMap, MapGuard};
+use parking_lot::Mutex;
+use std::{
+    collections::{hash_map::RandomState, VecDeque},
+    hash::{BuildHasher, Hash},
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+};
+#[cfg(feature = "std")]
+use std::{sync::Arc, time::Duration};
+
+/// The inserted value and the one it replaced or evicted.
+pub(crate) type Inserted<V> = (MapGuard<V>, Option<MapGuard<V>>);
+
+/// The backing store (database, file...) behind a [`WriteThroughCache`].
+pub trait WriteStore<K, V> {
+    type Error;
+
+    fn write(&self, key: &K, value: &V) -> Result<(), Self::Error>;
+
+    fn delete(&self, key: &K) -> Result<(), Self::Error>;
+}
+
+/// A cache writing every mutation to its store before applying it.
+///
+/// A mutation rejected by the store leaves the cache untouched. Mutations are
+/// serialized so the cache and the store always see them in the same order.
+pub struct WriteThroughCache<K, V, W, S = RandomState> {
+    map: FixedSizeLruMap<K, V, S>,
+    store: W,
+    writes: Mutex<()>,
+}
+
+impl<K, V, W> WriteThroughCache<K, V, W>
+where
+    K: Clone + Eq + Hash,
+    W: WriteStore<K, V>,
+{
+    pub fn new(capacity: usize, store: W) -> Self {
+        Self::with_map(FixedSizeLruMap::with_capacity(capacity), store)
+    }
+}
+
+impl<K, V, W, S> WriteThroughCache<K, V, W, S>
+where
+    K: Clone + Eq + Hash,
+    W: WriteStore<K, V>,
+    S: BuildHasher,
+{
+    pub fn with_map(map: FixedSizeLruMap<K, V, S>, store: W) -> Self {
+        WriteThroughCache {
+            map,
+            store,
+            writes: Mutex::new(()),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<MapGuard<V>> {
+        self.map.get(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Result<Inserted<V>, W::Error> {
+        let _writes = self.writes.lock();
+        self.store.write(&key, &value)?;
+        Ok(self.map.insert(key, value))
+    }
+
+    pub fn remove(&self, key: &K) -> Result<Option<MapGuard<V>>, W::Error> {
+        let _writes = self.writes.lock();
+        self.store.delete(key)?;
+        Ok(self.map.remove(key))
+    }
+
+    /// Returns the underlying map. Mutations made directly on it skip the store.
+    pub fn map(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.map
+    }
+
+    pub fn store(&self) -> &W {
+        &self.store
+    }
+}
+
+/// A mutation queued by a [`WriteBehindCache`].
+#[derive(Clone)]
+pub enum Write<K, V> {
+    Put(K, MapGuard<V>),
+    Delete(K),
+}
+
+/// Receives the batches of mutations of a [`WriteBehindCache`].
+pub trait WriteSink<K, V> {
+    type Error;
+
+    /// Persists `batch`, in order. On error, the whole batch is queued again.
+    fn write_batch(&self, batch: &[Write<K, V>]) -> Result<(), Self::Error>;
+}
+
+/// A cache applying the mutations immediately and writing them to its sink later, in batches.
+///
+/// The queue is written in batches of at most `batch_size` mutations on every
+/// [`flush`](Self::flush) and periodically with [`spawn_flusher`](Self::spawn_flusher),
+/// never on the thread of a mutation. Call `flush` before shutting down, the
+/// queued mutations are lost otherwise.
+///
+/// At most [`max_pending`](Self::max_pending) mutations wait in the queue, the
+/// oldest are dropped past that and counted by [`dropped`](Self::dropped).
+pub struct WriteBehindCache<K, V, W, S = RandomState> {
+    batch_size: usize,
+    dropped: AtomicU64,
+    flushing: Mutex<()>,
+    map: FixedSizeLruMap<K, V, S>,
+    max_pending: usize,
+    queue: Mutex<VecDeque<Write<K, V>>>,
+    sink: W,
+}
+
+impl<K, V, W> WriteBehindCache<K, V, W>
+where
+    K: Clone + Eq + Hash,
+    W: WriteSink<K, V>,
+{
+    pub fn new(capacity: usize, batch_size: usize, sink: W) -> Self {
+        Self::with_map(FixedSizeLruMap::with_capacity(capacity), batch_size, sink)
+    }
+}
+
+impl<K, V, W, S> WriteBehindCache<K, V, W, S>
+where
+    K: Clone + Eq + Hash,
+    W: WriteSink<K, V>,
+    S: BuildHasher,
+{
+    pub fn with_map(map: FixedSizeLruMap<K, V, S>, batch_size: usize, sink: W) -> Self {
+        let batch_size = batch_size.max(1);
+
+        WriteBehindCache {
+            batch_size,
+            dropped: AtomicU64::new(0),
+            flushing: Mutex::new(()),
+            map,
+            max_pending: batch_size.saturating_mul(DEFAULT_PENDING_BATCHES),
+            queue: Mutex::new(VecDeque::new()),
+            sink,
+        }
+    }
+
+    /// Returns the number of mutations dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Relaxed)
+    }
+
+    /// Writes every queued mutation to the sink, in batches.
+    ///
+    /// Stops at the first failed batch, which is queued again ahead of the
+    /// mutations made since.
+    pub fn flush(&self) -> Result<(), W::Error> {
+        let _flushing = self.flushing.lock();
+
+        loop {
+            let batch = {
+                let mut queue = self.queue.lock();
+                let n = queue.len().min(self.batch_size);
+                queue.drain(..n).collect::<Vec<_>>()
+            };
+
+            if batch.is_empty() {
+                return Ok(());
+            }
+
+            if let Err(e) = self.sink.write_batch(&batch) {
+                let mut queue = self.queue.lock();
+
+                for write in batch.into_iter().rev() {
+                    queue.push_front(write);
+                }
+
+                self.trim(&mut queue);
+                return Err(e);
+            }
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<MapGuard<V>> {
+        self.map.get(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) -> (MapGuard<V>, Option<MapGuard<V>>) {
+        self.queued(|| {
+            let (guard, old) = self.map.insert(key.clone(), value);
+            let write = Write::Put(key, guard.clone());
+            ((guard, old), write)
+        })
+    }
+
+    /// Returns the underlying map. Mutations made directly on it skip the sink.
+    pub fn map(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.map
+    }
+
+    /// Sets how many mutations can wait in the queue, `64 * batch_size` by default.
+    pub fn max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+
+    /// Returns the number of mutations waiting to be written.
+    pub fn pending(&self) -> usize {
+        self.queue.lock().len()
+    }
+
+    pub fn remove(&self, key: &K) -> Option<MapGuard<V>> {
+        self.queued(|| (self.map.remove(key), Write::Delete(key.clone())))
+    }
+
+    pub fn sink(&self) -> &W {
+        &self.sink
+    }
+
+    /// Flushes the queue every `interval` on a background thread until the
+    /// returned handle is dropped. Failed flushes are retried on the next tick.
+    #[cfg(feature = "std")]
+    pub fn spawn_flusher(self: &Arc<Self>, interval: Duration) -> MaintenanceHandle
+    where
+        K: Send + Sync + 'static,
+        V: Send + Sync + 'static,
+        W: Send + Sync + 'static,
+        S: Send + Sync + 'static,
+    {
+        spawn_periodic(self, interval, |cache| {
+            let _ = cache.flush();
+        })
+    }
+
+    /// Applies a mutation to the map and queues its write under the same lock,
+    /// so the sink receives the writes of a key in the order the map saw them.
+    fn queued<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce() -> (R, Write<K, V>),
+    {
+        let mut queue = self.queue.lock();
+        let (out, write) = f();
+        queue.push_back(write);
+        self.trim(&mut queue);
+        out
+    }
+
+    /// Drops the oldest mutations past `max_pending`.
+    fn trim(&self, queue: &mut VecDeque<Write<K, V>>) {
+        if let Some(excess) = queue.len().checked_sub(self.max_pending) {
+            queue.drain(..excess);
+            self.dropped.fetch_add(excess as u64, Relaxed);
+        }
+    }
+}
+
+/// The number of batches a [`WriteBehindCache`] queues by default.
+const DEFAULT_PENDING_BATCHES: usize = 64;
+
+#[test]
+fn test_write_through() {
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct Store(Mutex<HashMap<u32, char>>);
+
+    impl WriteStore<u32, char> for Store {
+        type Error = &'static str;
+
+        fn write(&self, key: &u32, value: &char) -> Result<(), Self::Error> {
+            if *value == '!' {
+                return Err("invalid");
+            }
+
+            self.0.lock().insert(*key, *value);
+            Ok(())
+        }
+
+        fn delete(&self, key: &u32) -> Result<(), Self::Error> {
+            self.0.lock().remove(key);
+            Ok(())
+        }
+    }
+
+    let cache = WriteThroughCache::new(1, Store::default());
+    cache.insert(1, 'a').unwrap();
+    cache.insert(2, 'b').unwrap();
+    assert!(cache.insert(3, '!').is_err());
+
+    // the cache evicted 1 but the store keeps everything.
+    assert!(cache.get(&1).is_none());
+    assert_eq!('b', *cache.get(&2).unwrap());
+    assert_eq!(2, cache.store().0.lock().len());
+
+    cache.remove(&2).unwrap();
+    assert_eq!(Some(&'a'), cache.store().0.lock().get(&1));
+    assert_eq!(1, cache.store().0.lock().len());
+}
+
+#[test]
+fn test_write_behind() {
+    #[derive(Default)]
+    struct Sink(Mutex<Vec<usize>>);
+
+    impl WriteSink<u32, char> for Sink {
+        type Error = ();
+
+        fn write_batch(&self, batch: &[Write<u32, char>]) -> Result<(), ()> {
+            self.0.lock().push(batch.len());
+            Ok(())
+        }
+    }
+
+    let cache = WriteBehindCache::new(10, 3, Sink::default());
+    cache.insert(1, 'a');
+    cache.insert(2, 'b');
+    assert_eq!(2, cache.pending());
+
+    // a full batch waits for the flusher instead of being written inline.
+    cache.remove(&1);
+    cache.insert(3, 'c');
+    assert_eq!(4, cache.pending());
+    assert!(cache.sink().0.lock().is_empty());
+
+    cache.flush().unwrap();
+    assert_eq!(vec![3, 1], *cache.sink().0.lock());
+    assert_eq!(0, cache.pending());
+}
+
+#[test]
+fn test_write_behind_overflow() {
+    struct Sink;
+
+    impl WriteSink<u32, char> for Sink {
+        type Error = ();
+
+        fn write_batch(&self, _: &[Write<u32, char>]) -> Result<(), ()> {
+            Err(())
+        }
+    }
+
+    let cache = WriteBehindCache::new(10, 2, Sink).max_pending(3);
+
+    for key in 0..5 {
+        cache.insert(key, 'a');
+    }
+
+    assert_eq!(3, cache.pending());
+    assert_eq!(2, cache.dropped());
+
+    // a failed batch is queued again within the bound.
+    assert!(cache.flush().is_err());
+    assert_eq!(3, cache.pending());
+    assert_eq!(2, cache.dropped());
+}
+
+#[test]
+fn test_write_behind_order() {
+    use std::{collections::HashMap, thread::scope};
+
+    #[derive(Default)]
+    struct Sink(Mutex<HashMap<u32, u32>>);
+
+    impl WriteSink<u32, u32> for Sink {
+        type Error = ();
+
+        fn write_batch(&self, batch: &[Write<u32, u32>]) -> Result<(), ()> {
+            let mut values = self.0.lock();
+
+            for write in batch {
+                if let Write::Put(k, v) = write {
+                    values.insert(*k, **v);
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    let cache = WriteBehindCache::new(10, 1000, Sink::default());
+
+    scope(|s| {
+        for n in 0..4 {
+            let cache = &cache;
+            s.spawn(move || (0..200).for_each(|i| drop(cache.insert(1, n * 1000 + i))));
+        }
+    });
+
+    cache.flush().unwrap();
+    assert_eq!(*cache.get(&1).unwrap(), cache.sink().0.lock()[&1]);
+}
@@ -0,0 +1,158 @@
+//! Invalidation of groups of entries sharing a tag.
+use crate::{FixedSizeLruMap, MapGuard, WeakMapGuard};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash},
+    sync::Arc,
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    /// Inserts a value carrying `tags`, so it can be removed along with the
+    /// other entries of a tag by [`invalidate_tag`](Self::invalidate_tag).
+    ///
+    /// The tags belong to this value only: replacing it, even through
+    /// `insert_tagged`, drops them.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// map.insert_tagged("invoice:1", 100, ["tenant:42"]);
+    /// map.insert_tagged("invoice:2", 250, ["tenant:42", "overdue"]);
+    /// map.insert_tagged("invoice:3", 75, ["tenant:7"]);
+    ///
+    /// assert_eq!(2, map.invalidate_tag("tenant:42"));
+    /// assert_eq!(1, map.len());
+    /// ```
+    pub fn insert_tagged<I, T>(
+        &self,
+        key: K,
+        value: V,
+        tags: I,
+    ) -> (MapGuard<V>, Option<MapGuard<V>>)
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let tags = tags.into_iter().map(Into::into).collect::<Vec<String>>();
+        let mut notices = Vec::new();
+
+        // the value is inserted and tagged under the index lock, so an
+        // invalidation of its tag cannot run in between and miss it.
+        let mut index = self.tags.0.lock();
+        let inserted = self.insert_locked(
+            &mut self.map.write(),
+            key.clone(),
+            Arc::new(value),
+            &mut notices,
+        );
+
+        for tag in tags {
+            let entries = index.entry(tag).or_default();
+
+            // forget the entries dropped since, so a tag never used for
+            // invalidation does not grow forever.
+            entries.retain(|(_, weak)| weak.0.strong_count() > 0);
+            entries.push((key.clone(), MapGuard::downgrade(&inserted.0)));
+        }
+
+        drop(index);
+        self.notify(notices);
+        inserted
+    }
+
+    /// Removes the entries inserted with `tag`, returning how many were still cached.
+    ///
+    /// A value tagged while the invalidation runs is inserted after it, and kept.
+    pub fn invalidate_tag(&self, tag: &str) -> usize {
+        let Some(entries) = self.tags.0.lock().remove(tag) else {
+            return 0;
+        };
+
+        entries
+            .into_iter()
+            .filter_map(|(key, weak)| Some((key, weak.upgrade()?)))
+            .filter(|(key, tagged)| {
+                self.remove_if(key, |v| MapGuard::ptr_eq(v, tagged))
+                    .is_some()
+            })
+            .count()
+    }
+}
+
+impl<K, V, S> FixedSizeLruMap<K, V, S> {
+    /// Drops the tagged entries no longer cached, and the tags left empty.
+    pub(crate) fn prune_tags(&self) {
+        self.tags.0.lock().retain(|_, entries| {
+            entries.retain(|(_, weak)| weak.0.strong_count() > 0);
+            !entries.is_empty()
+        });
+    }
+}
+
+/// An entry identified by its key and its guard, so a value replacing a
+/// tagged one is not mistaken for it.
+type Tagged<K, V> = (K, WeakMapGuard<V>);
+
+/// The entries of each tag.
+pub(crate) struct Tags<K, V>(Mutex<HashMap<String, Vec<Tagged<K, V>>>>);
+
+impl<K, V> Default for Tags<K, V> {
+    fn default() -> Self {
+        Tags(Mutex::new(HashMap::new()))
+    }
+}
+
+#[test]
+fn test_invalidate_tag() {
+    let map = FixedSizeLruMap::with_capacity(2);
+    map.insert_tagged(1, "a", ["x"]);
+    map.insert_tagged(2, "b", ["x", "y"]);
+
+    // the replaced value loses its tags.
+    map.insert(1, "c");
+    assert_eq!(1, map.invalidate_tag("x"));
+    assert_eq!("c", *map.get(&1).unwrap());
+    assert_eq!(0, map.invalidate_tag("y"));
+    assert_eq!(0, map.invalidate_tag("z"));
+}
+
+#[test]
+fn test_prune_tags() {
+    let map = FixedSizeLruMap::with_capacity(1);
+    map.insert_tagged(1, "a", ["x"]);
+
+    // 1 is evicted, leaving its tag behind until the maintenance.
+    map.insert(2, "b");
+    map.run_maintenance();
+    assert!(map.tags.0.lock().is_empty());
+}
+
+#[test]
+fn test_invalidate_tag_during_insert() {
+    use std::{thread::scope, time::Duration};
+
+    let map = FixedSizeLruMap::with_capacity(10);
+
+    let invalidated = scope(|s| {
+        // hold the insert halfway, before its value reaches the map.
+        let write = map.map.write();
+        s.spawn(|| map.insert_tagged(1, "a", ["x"]));
+        std::thread::sleep(Duration::from_millis(20));
+
+        let invalidated = s.spawn(|| map.invalidate_tag("x"));
+        std::thread::sleep(Duration::from_millis(20));
+        drop(write);
+        invalidated.join().unwrap()
+    });
+
+    // the invalidation waits for the value to be tagged instead of missing it.
+    assert_eq!(1, invalidated);
+    assert!(!map.contains_key(&1));
+}
@@ -0,0 +1,259 @@
+//! A cache partitioned between tenants, so none can evict the entries of another.
+use crate::{FixedSizeLruMap, MapGuard, Pair};
+use parking_lot::RwLock;
+use std::{collections::HashMap, hash::Hash, sync::Arc};
+
+/// A map dividing a global capacity between tenants.
+///
+/// A tenant given a quota has a map of its own of that capacity, reserved out
+/// of the global one, so its entries are only ever evicted by its own inserts.
+/// The other tenants share the capacity left, where a noisy one can only evict
+/// the entries of the tenants without a quota.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::MultiTenantLruMap;
+///
+/// let map = MultiTenantLruMap::with_capacity(3);
+/// assert!(map.set_quota("acme", 1));
+///
+/// map.insert("acme", 1, "a");
+///
+/// // "noisy" fills the capacity left without touching the entry of "acme".
+/// for key in 0..10 {
+///     map.insert("noisy", key, "n");
+/// }
+///
+/// assert_eq!("a", *map.get(&"acme", &1).unwrap());
+/// assert_eq!(2, map.tenant_len(&"noisy"));
+/// ```
+pub struct MultiTenantLruMap<T, K, V> {
+    capacity: usize,
+    state: RwLock<State<T, K, V>>,
+}
+
+struct State<T, K, V> {
+    quotas: HashMap<T, Arc<FixedSizeLruMap<K, V>>>,
+    reserved: usize,
+    shared: Arc<FixedSizeLruMap<(T, K), V>>,
+}
+
+/// The map holding the entries of a tenant.
+enum Partition<T, K, V> {
+    Own(Arc<FixedSizeLruMap<K, V>>),
+    Shared(Arc<FixedSizeLruMap<(T, K), V>>),
+}
+
+impl<T, K, V> MultiTenantLruMap<T, K, V>
+where
+    K: Clone + Eq + Hash,
+    T: Clone + Eq + Hash,
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        MultiTenantLruMap {
+            capacity,
+            state: RwLock::new(State {
+                quotas: HashMap::new(),
+                reserved: 0,
+                shared: Arc::new(timed_map(capacity)),
+            }),
+        }
+    }
+
+    pub fn get(&self, tenant: &T, key: &K) -> Option<MapGuard<V>> {
+        match self.partition(tenant) {
+            Partition::Own(map) => map.get(key),
+            Partition::Shared(map) => map.get(&Pair(tenant, key)),
+        }
+    }
+
+    pub fn get_or_init<F>(&self, tenant: T, key: K, f: F) -> MapGuard<V>
+    where
+        F: FnOnce() -> V,
+    {
+        match self.partition(&tenant) {
+            Partition::Own(map) => map.get_or_init(key, f),
+            Partition::Shared(map) => map.get_or_init((tenant, key), f),
+        }
+    }
+
+    pub fn insert(&self, tenant: T, key: K, value: V) -> (MapGuard<V>, Option<MapGuard<V>>) {
+        match self.partition(&tenant) {
+            Partition::Own(map) => map.insert(key, value),
+            Partition::Shared(map) => map.insert((tenant, key), value),
+        }
+    }
+
+    /// Removes every entry of `tenant`, keeping its quota.
+    pub fn invalidate_tenant(&self, tenant: &T) {
+        match self.partition(tenant) {
+            Partition::Own(map) => map.clear(),
+            Partition::Shared(map) => {
+                for key in tenant_keys(&map, tenant) {
+                    map.remove(&key);
+                }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries across all the tenants.
+    pub fn len(&self) -> usize {
+        let state = self.state.read();
+        state.shared.len() + state.quotas.values().map(|m| m.len()).sum::<usize>()
+    }
+
+    pub fn remove(&self, tenant: &T, key: &K) -> Option<MapGuard<V>> {
+        match self.partition(tenant) {
+            Partition::Own(map) => map.remove(key),
+            Partition::Shared(map) => map.remove(&Pair(tenant, key)),
+        }
+    }
+
+    /// Reserves `quota` entries of the capacity for `tenant`, or gives its
+    /// reservation back to the shared capacity with a quota of 0.
+    ///
+    /// Returns false, changing nothing, when the quotas would exceed the capacity.
+    /// The most recently used entries of the tenant are kept, as many as fit,
+    /// but the values inserted while the quota changes may be lost.
+    pub fn set_quota(&self, tenant: T, quota: usize) -> bool {
+        let mut state = self.state.write();
+        let previous = state.quotas.get(&tenant).map_or(0, |m| m.capacity());
+        let reserved = state.reserved - previous + quota;
+
+        if reserved > self.capacity {
+            return false;
+        }
+
+        let old = state.quotas.remove(&tenant);
+        let shared = Arc::clone(&state.shared);
+
+        // the entries of the tenant, from the least to the most recently used.
+        let mut entries = match &old {
+            Some(map) => map.entries_by_recency(),
+            None => Vec::new(),
+        };
+
+        for (t, k) in tenant_keys(&shared, &tenant) {
+            if let Some(v) = shared.remove(&Pair(&t, &k)) {
+                entries.push((k, v));
+            }
+        }
+
+        // the ages of two maps do not compare, their last access times do. The
+        // sort is stable, the entries of a map accessed at once keep their order.
+        let recency = |v: &MapGuard<V>| MapGuard::info(v).last_accessed_at;
+        entries.sort_by_key(|(_, v)| recency(v));
+
+        let rebuilt = timed_map(self.capacity - reserved);
+        let mut kept = shared.entries_by_recency();
+
+        if quota > 0 {
+            let own = timed_map(quota);
+
+            for (k, v) in entries {
+                own.insert_arc(k, MapGuard::into_arc(v));
+            }
+
+            state.quotas.insert(tenant, Arc::new(own));
+        } else {
+            kept.extend(entries.into_iter().map(|(k, v)| ((tenant.clone(), k), v)));
+            kept.sort_by_key(|(_, v)| recency(v));
+        }
+
+        for (k, v) in kept {
+            rebuilt.insert_arc(k, MapGuard::into_arc(v));
+        }
+
+        state.reserved = reserved;
+        state.shared = Arc::new(rebuilt);
+        true
+    }
+
+    /// Returns the number of entries of `tenant`.
+    pub fn tenant_len(&self, tenant: &T) -> usize {
+        match self.partition(tenant) {
+            Partition::Own(map) => map.len(),
+            Partition::Shared(map) => tenant_keys(&map, tenant).len(),
+        }
+    }
+
+    fn partition(&self, tenant: &T) -> Partition<T, K, V> {
+        let state = self.state.read();
+
+        match state.quotas.get(tenant) {
+            Some(map) => Partition::Own(Arc::clone(map)),
+            None => Partition::Shared(Arc::clone(&state.shared)),
+        }
+    }
+}
+
+/// Creates a map recording the access times, which order the entries of several maps.
+fn timed_map<K, V>(capacity: usize) -> FixedSizeLruMap<K, V>
+where
+    K: Eq + Hash,
+{
+    FixedSizeLruMap::builder(capacity).timestamps(true).build()
+}
+
+fn tenant_keys<T, K, V>(map: &FixedSizeLruMap<(T, K), V>, tenant: &T) -> Vec<(T, K)>
+where
+    K: Clone + Eq + Hash,
+    T: Clone + Eq + Hash,
+{
+    map.view()
+        .keys()
+        .filter(|(t, _)| t == tenant)
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_tenant_quotas() {
+    let map = MultiTenantLruMap::with_capacity(4);
+    map.insert("a", 1, 'x');
+    map.insert("a", 2, 'y');
+    map.insert("b", 1, 'z');
+
+    // the entries of "a" move to its own map, the most recent first.
+    assert!(map.set_quota("a", 1));
+    assert!(!map.set_quota("b", 4));
+    assert_eq!(1, map.tenant_len(&"a"));
+    assert_eq!('y', *map.get(&"a", &2).unwrap());
+    assert_eq!('z', *map.get(&"b", &1).unwrap());
+
+    for key in 0..10 {
+        map.insert("b", key, 'b');
+    }
+
+    assert_eq!(1, map.tenant_len(&"a"));
+    assert_eq!(3, map.tenant_len(&"b"));
+
+    // giving the quota back moves the entries to the shared map.
+    assert!(map.set_quota("a", 0));
+    assert_eq!(1, map.tenant_len(&"a"));
+    assert_eq!(4, map.len());
+
+    map.invalidate_tenant(&"b");
+    assert_eq!(1, map.len());
+}
+
+#[test]
+fn test_quota_release_by_recency() {
+    use std::{thread::sleep, time::Duration};
+
+    let map = MultiTenantLruMap::with_capacity(2);
+    assert!(map.set_quota("a", 1));
+    map.insert("a", 1, 'a');
+    sleep(Duration::from_millis(2));
+    map.insert("b", 1, 'b');
+
+    // the entry of "a" is older than the shared one, it is evicted first.
+    assert!(map.set_quota("a", 0));
+    map.insert("c", 1, 'c');
+    assert!(map.get(&"a", &1).is_none());
+    assert!(map.get(&"b", &1).is_some());
+}
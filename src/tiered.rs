@@ -0,0 +1,339 @@
+//! A two-tier cache where each thread keeps a small private L1 in front of a
+//! shared [`FixedSizeLruMap`].
+use crate::{FixedSizeLruMap, MapGuard};
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::hash_map::{HashMap, RandomState},
+    hash::{BuildHasher, Hash},
+    sync::{
+        atomic::{
+            AtomicU64, AtomicUsize,
+            Ordering::{Relaxed, SeqCst},
+        },
+        Arc, Weak,
+    },
+};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static LOCALS: RefCell<HashMap<usize, LocalSlot>> = RefCell::new(HashMap::new());
+}
+
+/// A cache reading from a thread-local L1 before falling back to the shared L2.
+///
+/// The L1 is only touched by its owning thread so a hit takes no lock at all.
+/// Replacing or removing a value through this map invalidates every L1, which
+/// refreshes itself from the L2 on the next access.
+pub struct TieredLruMap<K, V, S = RandomState> {
+    alive: Arc<()>,
+    epoch: AtomicU64,
+    id: usize,
+    l1_capacity: usize,
+    l2: FixedSizeLruMap<K, V, S>,
+}
+
+impl<K, V> TieredLruMap<K, V>
+where
+    K: Clone + Eq + Hash + 'static,
+    V: 'static,
+{
+    pub fn with_capacity(l1_capacity: usize, l2_capacity: usize) -> Self {
+        Self::with_l2(l1_capacity, FixedSizeLruMap::with_capacity(l2_capacity))
+    }
+}
+
+impl<K, V, S> TieredLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash + 'static,
+    V: 'static,
+    S: BuildHasher,
+{
+    pub fn with_l2(l1_capacity: usize, l2: FixedSizeLruMap<K, V, S>) -> Self {
+        TieredLruMap {
+            alive: Arc::new(()),
+            epoch: AtomicU64::new(0),
+            id: NEXT_ID.fetch_add(1, Relaxed),
+            l1_capacity,
+            l2,
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<MapGuard<V>> {
+        if let Some(guard) = self.with_local(|l1| l1.get(key)) {
+            self.l2.update_guard_age(&guard);
+            return Some(guard);
+        }
+
+        let epoch = self.epoch.load(SeqCst);
+        let guard = self.l2.get(key)?;
+        self.cache_local(epoch, key.clone(), guard.clone());
+        Some(guard)
+    }
+
+    pub fn get_or_init<F>(&self, key: K, f: F) -> MapGuard<V>
+    where
+        F: FnOnce() -> V,
+    {
+        match self.get(&key) {
+            Some(guard) => guard,
+            None => {
+                let value = self.l2.load(&key, f);
+                // another thread may have filled the key while loading, keep its value.
+                self.store(key, value, false).0
+            }
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) -> (MapGuard<V>, Option<MapGuard<V>>) {
+        self.store(key, value, true)
+    }
+
+    /// Inserts into the L2 and the local L1, bumping the epoch when the key
+    /// itself is replaced. Without `replace`, a present value is kept and returned.
+    fn store(&self, key: K, value: V, replace: bool) -> (MapGuard<V>, Option<MapGuard<V>>) {
+        let mut epoch = self.epoch.load(SeqCst);
+        let mut notices = Vec::new();
+
+        let (guard, old, replaced) = {
+            let mut map = self.l2.map.write();
+
+            match map.get(&key) {
+                Some(cur) if !replace => (cur.clone(), None, false),
+                cur => {
+                    let replaced = cur.is_some();
+                    let (guard, old) =
+                        self.l2
+                            .insert_locked(&mut map, key.clone(), Arc::new(value), &mut notices);
+                    (guard, old, replaced)
+                }
+            }
+        };
+
+        self.l2.notify(notices);
+
+        // a new key cannot be stale in any L1, only a replaced one can; an eviction
+        // of another key is not. Our own invalidation does not make the guard stale,
+        // one from another thread does.
+        if replaced && self.epoch.fetch_add(1, SeqCst) == epoch {
+            epoch += 1;
+        }
+
+        self.cache_local(epoch, key, guard.clone());
+        (guard, old)
+    }
+
+    pub fn remove(&self, key: &K) -> Option<MapGuard<V>> {
+        let old = self.l2.remove(key);
+
+        if old.is_some() {
+            self.invalidate_all();
+        }
+
+        old
+    }
+
+    /// Returns the shared L2 map.
+    ///
+    /// Mutations made directly on the L2 are not propagated to the L1 caches.
+    pub fn l2(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.l2
+    }
+
+    /// Discards the L1 of every thread.
+    pub fn invalidate_all(&self) {
+        self.epoch.fetch_add(1, SeqCst);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.l2.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.l2.len()
+    }
+
+    /// Stores a guard read from the L2 unless an invalidation happened since `epoch`.
+    fn cache_local(&self, epoch: u64, key: K, guard: MapGuard<V>) {
+        self.with_local(|l1| {
+            if l1.epoch == epoch {
+                l1.insert(key, guard);
+            }
+        });
+    }
+
+    fn with_local<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut LocalLru<K, V>) -> R,
+    {
+        LOCALS.with(|locals| {
+            let mut locals = locals.borrow_mut();
+
+            if !locals.contains_key(&self.id) {
+                // take the opportunity to drop the L1 of maps that no longer exist.
+                locals.retain(|_, slot| slot.alive.strong_count() > 0);
+                locals.insert(
+                    self.id,
+                    LocalSlot {
+                        alive: Arc::downgrade(&self.alive),
+                        lru: Box::new(LocalLru::<K, V>::new(self.l1_capacity)),
+                    },
+                );
+            }
+
+            let l1 = locals
+                .get_mut(&self.id)
+                .and_then(|slot| slot.lru.downcast_mut::<LocalLru<K, V>>())
+                .expect("l1 type");
+
+            let epoch = self.epoch.load(SeqCst);
+
+            if l1.epoch != epoch {
+                l1.map.clear();
+                l1.epoch = epoch;
+            }
+
+            f(l1)
+        })
+    }
+}
+
+struct LocalSlot {
+    alive: Weak<()>,
+    lru: Box<dyn Any>,
+}
+
+struct LocalLru<K, V> {
+    age: u64,
+    capacity: usize,
+    epoch: u64,
+    map: HashMap<K, (u64, MapGuard<V>)>,
+}
+
+impl<K, V> LocalLru<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    fn new(capacity: usize) -> Self {
+        LocalLru {
+            age: 0,
+            capacity,
+            epoch: 0,
+            map: HashMap::with_capacity(capacity + 1),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<MapGuard<V>> {
+        let (age, guard) = self.map.get_mut(key)?;
+        self.age += 1;
+        *age = self.age;
+        Some(guard.clone())
+    }
+
+    fn insert(&mut self, key: K, guard: MapGuard<V>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.age += 1;
+
+        if self.map.insert(key, (self.age, guard)).is_none() && self.map.len() > self.capacity {
+            if let Some(key) = self
+                .map
+                .iter()
+                .min_by_key(|(_, (age, _))| *age)
+                .map(|(k, _)| k.clone())
+            {
+                self.map.remove(&key);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tiered_invalidation() {
+    use std::thread::spawn;
+
+    let map = Arc::new(TieredLruMap::with_capacity(2, 10));
+    map.insert(1, 'a');
+    assert_eq!('a', *map.get(&1).unwrap());
+
+    let other = map.clone();
+    spawn(move || {
+        assert_eq!('a', *other.get(&1).unwrap());
+        other.insert(1, 'b');
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!('b', *map.get(&1).unwrap());
+    map.remove(&1);
+    assert!(map.get(&1).is_none());
+}
+
+#[test]
+fn test_tiered_concurrent_replace() {
+    use std::{sync::Barrier, thread::spawn};
+
+    for _ in 0..100 {
+        let map = Arc::new(TieredLruMap::with_capacity(2, 10));
+        let barrier = Arc::new(Barrier::new(2));
+        map.insert(1, 0);
+
+        let other = map.clone();
+        let other_barrier = barrier.clone();
+        let handle = spawn(move || {
+            other_barrier.wait();
+            other.insert(1, 2);
+        });
+
+        barrier.wait();
+        map.insert(1, 1);
+        handle.join().unwrap();
+
+        // the L1 must never keep the value replaced by the other thread.
+        assert_eq!(*map.l2().get(&1).unwrap(), *map.get(&1).unwrap());
+    }
+}
+
+#[test]
+fn test_tiered_get_or_init_race() {
+    use std::{sync::Barrier, thread::spawn};
+
+    let map = Arc::new(TieredLruMap::with_capacity(2, 10));
+    let barrier = Arc::new(Barrier::new(2));
+
+    let other = map.clone();
+    let other_barrier = barrier.clone();
+    let handle = spawn(move || {
+        *other.get_or_init(1, || {
+            other_barrier.wait();
+            other_barrier.wait();
+            1
+        })
+    });
+
+    barrier.wait();
+    map.insert(1, 2);
+    assert_eq!(2, *map.get(&1).unwrap());
+    barrier.wait();
+
+    // the load finishing last must neither replace the value nor leave a stale L1.
+    assert_eq!(2, handle.join().unwrap());
+    assert_eq!(2, *map.l2().get(&1).unwrap());
+    assert_eq!(2, *map.get(&1).unwrap());
+}
+
+#[test]
+fn test_tiered_eviction_keeps_epoch() {
+    let map = TieredLruMap::with_capacity(2, 1);
+    map.insert(1, 'a');
+    let (_, evicted) = map.insert(2, 'b');
+
+    assert_eq!('a', *evicted.unwrap());
+    assert_eq!(0, map.epoch.load(SeqCst));
+
+    map.insert(2, 'c');
+    assert_eq!(1, map.epoch.load(SeqCst));
+}
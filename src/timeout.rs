@@ -0,0 +1,101 @@
+//! Initialization bounded in time.
+use crate::{
+    future::{block_on, block_on_until},
+    FixedSizeLruMap, MapGuard,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    hash::{BuildHasher, Hash},
+    sync::{mpsc::sync_channel, Arc},
+    thread::spawn,
+    time::{Duration, Instant},
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash + Send + Sync + 'static,
+    V: Send + Sync + 'static,
+    S: BuildHasher + Send + Sync + 'static,
+{
+    /// Gets the value of `key` or initializes it with `f`, giving up after `timeout`.
+    ///
+    /// `f` runs on another thread and shares the in-flight load of the key with
+    /// the async callers, so the timeout also bounds the wait on another caller's
+    /// load. Such a wait happens on the calling thread, only a call starting the
+    /// load spawns a thread, so a key takes at most one thread at a time.
+    ///
+    /// A thread cannot be interrupted: a timed out `f` keeps running until it
+    /// returns, and its value is cached then.
+    pub fn get_or_init_timeout<F>(
+        self: &Arc<Self>,
+        key: K,
+        timeout: Duration,
+        f: F,
+    ) -> Result<MapGuard<V>, Timeout>
+    where
+        F: FnOnce() -> V + Send + 'static,
+    {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(value) = self.get(&key) {
+                return Ok(value);
+            }
+
+            let Some(load) = self.loads.get(&key) else {
+                break;
+            };
+
+            // a cancelled load leaves the key to the next caller.
+            match block_on_until(load.wait(), Some(deadline)) {
+                Some(Some(value)) => return Ok(value),
+                Some(None) => continue,
+                None => return Err(Timeout),
+            }
+        }
+
+        let map = Arc::clone(self);
+        let (tx, rx) = sync_channel(1);
+
+        spawn(move || {
+            let value = block_on(map.get_or_init_async(key, || async { f() }));
+            let _ = tx.send(value);
+        });
+
+        rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .map_err(|_| Timeout)
+    }
+}
+
+/// The initialization did not complete in time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Timeout;
+
+impl Display for Timeout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("initialization timed out")
+    }
+}
+
+impl Error for Timeout {}
+
+#[test]
+fn test_get_or_init_timeout() {
+    use std::thread::sleep;
+
+    let map = Arc::new(FixedSizeLruMap::with_capacity(2));
+    let slow = map.get_or_init_timeout(1, Duration::from_millis(10), || {
+        sleep(Duration::from_millis(100));
+        'a'
+    });
+    assert_eq!(Err(Timeout), slow.map(|v| *v));
+
+    // waits on the calling thread, without starting another load.
+    let v = map.get_or_init_timeout(1, Duration::from_millis(10), || 'c');
+    assert_eq!(Err(Timeout), v.map(|v| *v));
+
+    // waits on the load already in flight instead of starting another one.
+    let v = map.get_or_init_timeout(1, Duration::from_secs(5), || 'b');
+    assert_eq!('a', *v.unwrap());
+}
@@ -0,0 +1,131 @@
+//! Recording of the accesses to a map, to replay real workloads offline.
+#[cfg(feature = "trace")]
+use parking_lot::Mutex;
+#[cfg(feature = "trace")]
+use std::{collections::VecDeque, time::Instant};
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+/// The kind of an [`Access`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AccessOp {
+    /// A lookup that found a value.
+    Hit,
+    /// A lookup that found nothing.
+    Miss,
+    Insert,
+    Remove,
+}
+
+/// An access to a map, identifying the key by its hash.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Access {
+    /// Time elapsed since the recording started.
+    pub at: Duration,
+    pub key_hash: u64,
+    pub op: AccessOp,
+}
+
+impl AccessOp {
+    fn as_str(self) -> &'static str {
+        match self {
+            AccessOp::Hit => "hit",
+            AccessOp::Miss => "miss",
+            AccessOp::Insert => "insert",
+            AccessOp::Remove => "remove",
+        }
+    }
+}
+
+/// Writes `trace` as `nanos,op,key_hash` lines.
+pub fn write_trace<W: Write>(mut w: W, trace: &[Access]) -> io::Result<()> {
+    for a in trace {
+        writeln!(w, "{},{},{}", a.at.as_nanos(), a.op.as_str(), a.key_hash)?;
+    }
+
+    Ok(())
+}
+
+/// Keeps the most recent accesses of a map in a ring buffer.
+///
+/// Without the `trace` feature, this is empty and recording is free.
+#[derive(Default)]
+pub(crate) struct TraceRecorder {
+    #[cfg(feature = "trace")]
+    ring: Option<Ring>,
+}
+
+#[cfg(feature = "trace")]
+struct Ring {
+    accesses: Mutex<VecDeque<Access>>,
+    capacity: usize,
+    start: Instant,
+}
+
+impl TraceRecorder {
+    #[cfg(feature = "trace")]
+    pub fn new(capacity: usize) -> Self {
+        TraceRecorder {
+            ring: Some(Ring {
+                accesses: Mutex::new(VecDeque::with_capacity(capacity)),
+                capacity,
+                start: Instant::now(),
+            }),
+        }
+    }
+
+    /// Records an access, `key_hash` is only called when recording.
+    #[inline]
+    pub fn record<F>(&self, _op: AccessOp, _key_hash: F)
+    where
+        F: FnOnce() -> u64,
+    {
+        #[cfg(feature = "trace")]
+        if let Some(ring) = &self.ring {
+            let access = Access {
+                at: ring.start.elapsed(),
+                key_hash: _key_hash(),
+                op: _op,
+            };
+
+            let mut accesses = ring.accesses.lock();
+
+            if accesses.len() == ring.capacity {
+                accesses.pop_front();
+            }
+
+            accesses.push_back(access);
+        }
+    }
+
+    #[cfg(feature = "trace")]
+    pub fn snapshot(&self) -> Vec<Access> {
+        self.ring
+            .as_ref()
+            .map(|r| r.accesses.lock().iter().copied().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn test_trace() {
+    use crate::FixedSizeLruMap;
+
+    let map = FixedSizeLruMap::builder(2).trace(3).build();
+    map.get(&1);
+    map.insert(1, 'a');
+    map.get(&1);
+    map.remove(&1);
+
+    let trace = map.trace();
+    let ops = trace.iter().map(|a| a.op).collect::<Vec<_>>();
+    assert_eq!(vec![AccessOp::Insert, AccessOp::Hit, AccessOp::Remove], ops);
+    assert!(trace.iter().all(|a| a.key_hash == trace[0].key_hash));
+
+    let mut out = Vec::new();
+    write_trace(&mut out, &trace).unwrap();
+    assert_eq!(3, String::from_utf8(out).unwrap().lines().count());
+}
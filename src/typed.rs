@@ -0,0 +1,116 @@
+//! A cache of values of any type, each key being scoped to the type of its value.
+use crate::{FixedSizeLruMap, MapGuard, MappedMapGuard, Pair};
+use std::{
+    any::{Any, TypeId},
+    hash::Hash,
+};
+
+type AnyValue = Box<dyn Any + Send + Sync>;
+
+/// A lru map shared by values of different types.
+///
+/// The type of the value is part of the key, so the same key can hold one
+/// value per type and a lookup never returns a value of another type. All the
+/// types compete for the same capacity.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::TypedLruCache;
+///
+/// let cache = TypedLruCache::with_capacity(10);
+/// cache.insert("answer", 42u32);
+/// cache.insert("answer", "forty-two".to_owned());
+///
+/// assert_eq!(42, *cache.get::<u32>(&"answer").unwrap());
+/// assert_eq!("forty-two", *cache.get::<String>(&"answer").unwrap());
+/// assert!(cache.get::<i64>(&"answer").is_none());
+/// ```
+pub struct TypedLruCache<K> {
+    map: FixedSizeLruMap<(TypeId, K), AnyValue>,
+}
+
+impl<K> TypedLruCache<K>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        TypedLruCache {
+            map: FixedSizeLruMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn contains_key<T: Any>(&self, key: &K) -> bool {
+        self.map.contains_key(&Pair(&TypeId::of::<T>(), key))
+    }
+
+    pub fn get<T>(&self, key: &K) -> Option<MappedMapGuard<T>>
+    where
+        T: Any + Send + Sync,
+    {
+        downcast(self.map.get(&Pair(&TypeId::of::<T>(), key))?)
+    }
+
+    pub fn get_or_init<T, F>(&self, key: K, f: F) -> MappedMapGuard<T>
+    where
+        F: FnOnce() -> T,
+        T: Any + Send + Sync,
+    {
+        let guard = self
+            .map
+            .get_or_init((TypeId::of::<T>(), key), || Box::new(f()));
+
+        downcast(guard).expect("value of the type of its key")
+    }
+
+    pub fn insert<T>(&self, key: K, value: T) -> MappedMapGuard<T>
+    where
+        T: Any + Send + Sync,
+    {
+        let (guard, _) = self.map.insert((TypeId::of::<T>(), key), Box::new(value));
+        downcast(guard).expect("value of the type of its key")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the underlying map, keyed by the type of the values and their key.
+    pub fn map(&self) -> &FixedSizeLruMap<(TypeId, K), AnyValue> {
+        &self.map
+    }
+
+    pub fn remove<T>(&self, key: &K) -> Option<MappedMapGuard<T>>
+    where
+        T: Any + Send + Sync,
+    {
+        downcast(self.map.remove(&Pair(&TypeId::of::<T>(), key))?)
+    }
+}
+
+fn downcast<T>(guard: MapGuard<AnyValue>) -> Option<MappedMapGuard<T>>
+where
+    T: Any + Send + Sync,
+{
+    MapGuard::try_map(guard, |v| v.downcast_ref::<T>()).ok()
+}
+
+#[test]
+fn test_typed_lru_cache() {
+    let cache = TypedLruCache::with_capacity(2);
+    cache.insert(1, 1u8);
+    assert_eq!("one", *cache.get_or_init(1, || "one"));
+    assert_eq!("one", *cache.get_or_init(1, || "uno"));
+    assert!(cache.contains_key::<u8>(&1));
+
+    // the u8 value is the least recently used.
+    cache.insert(2, 2u8);
+    assert!(!cache.contains_key::<u8>(&1));
+    assert_eq!(2, cache.len());
+
+    assert_eq!("one", *cache.remove::<&str>(&1).unwrap());
+    assert!(cache.remove::<&str>(&1).is_none());
+}
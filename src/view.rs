@@ -0,0 +1,111 @@
+//! Borrowed iteration over a locked map.
+use crate::{Equivalent, FixedSizeLruMap, MapGuard};
+use parking_lot::RwLockReadGuard;
+use std::{
+    borrow::Borrow,
+    collections::{hash_map, HashMap},
+    hash::{BuildHasher, Hash},
+};
+
+/// A read locked view of a map, returned by [`view`](FixedSizeLruMap::view).
+///
+/// Writers wait until the view is dropped, it should be short lived.
+pub struct MapView<'a, K, V, S>(RwLockReadGuard<'a, HashMap<K, MapGuard<V>, S>>);
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Locks the map for reading to iterate over its entries by reference.
+    ///
+    /// Unlike [`entries`](Self::entries), the keys are not cloned, which matters
+    /// for large keys such as byte buffers. Reading through a view does not
+    /// count as a use of the entries.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::FixedSizeLruMap;
+    ///
+    /// let map = FixedSizeLruMap::with_capacity(10);
+    /// map.insert(vec![0u8; 4096], 1);
+    /// map.insert(vec![1u8; 4096], 2);
+    ///
+    /// let sum: i32 = map.view().iter().map(|(_, v)| **v).sum();
+    /// assert_eq!(3, sum);
+    /// ```
+    pub fn view(&self) -> MapView<'_, K, V, S> {
+        MapView(self.map.read())
+    }
+}
+
+impl<K, V, S> MapView<'_, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Returns the value of `key`, without updating its recency.
+    pub fn get<Q>(&self, key: &Q) -> Option<&MapGuard<V>>
+    where
+        K: Borrow<Q::Key>,
+        Q: Equivalent<K> + ?Sized,
+    {
+        self.0.get(key.as_key())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the entries in no particular order.
+    pub fn iter(&self) -> hash_map::Iter<'_, K, MapGuard<V>> {
+        self.0.iter()
+    }
+
+    pub fn keys(&self) -> hash_map::Keys<'_, K, MapGuard<V>> {
+        self.0.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<'v, K, V, S> IntoIterator for &'v MapView<'_, K, V, S> {
+    type Item = (&'v K, &'v MapGuard<V>);
+    type IntoIter = hash_map::Iter<'v, K, MapGuard<V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[test]
+fn test_view() {
+    use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+    static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Eq, Hash, PartialEq)]
+    struct Key(u8);
+
+    impl Clone for Key {
+        fn clone(&self) -> Self {
+            CLONES.fetch_add(1, Relaxed);
+            Key(self.0)
+        }
+    }
+
+    let map = FixedSizeLruMap::with_capacity(2);
+    map.insert(Key(1), 'a');
+    map.insert(Key(2), 'b');
+
+    let clones = CLONES.load(Relaxed);
+    let view = map.view();
+    let mut keys = (&view).into_iter().map(|(k, _)| k.0).collect::<Vec<_>>();
+    keys.sort();
+
+    assert_eq!(vec![1, 2], keys);
+    assert_eq!(Some('b'), view.get(&Key(2)).map(|v| **v));
+    assert_eq!(clones, CLONES.load(Relaxed));
+}
@@ -0,0 +1,237 @@
+//! Persistence of a cache through an append-only write-ahead log.
+use crate::{
+    persist::{replace_file, Persist},
+    store::Inserted,
+    FixedSizeLruMap, MapGuard,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::hash_map::RandomState,
+    fs::{File, OpenOptions},
+    hash::{BuildHasher, Hash},
+    io::{self, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+const INSERT: u8 = 0;
+const REMOVE: u8 = 1;
+
+/// A cache appending every insert and remove to a log, replayed when the cache
+/// is opened again so its content survives a crash.
+///
+/// The log is compacted into the current entries, in recency order, once it
+/// holds more records than [`compact_after`](Self::compact_after).
+/// Mutations are serialized so the log and the map see them in the same order.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::WalCache;
+///
+/// let path = std::env::temp_dir().join("fixed_size_lru_map_doc.wal");
+/// # let _ = std::fs::remove_file(&path);
+/// let cache = WalCache::open(&path, 10).unwrap();
+/// cache.insert(1u32, "a".to_owned()).unwrap();
+/// drop(cache);
+///
+/// let cache = WalCache::<u32, String>::open(&path, 10).unwrap();
+/// assert_eq!("a", *cache.get(&1).unwrap());
+/// # std::fs::remove_file(path).unwrap();
+/// ```
+pub struct WalCache<K, V, S = RandomState> {
+    compact_after: usize,
+    log: Mutex<Log>,
+    map: FixedSizeLruMap<K, V, S>,
+    path: PathBuf,
+}
+
+struct Log {
+    file: File,
+    records: usize,
+}
+
+impl<K, V> WalCache<K, V>
+where
+    K: Clone + Eq + Hash + Persist,
+    V: Persist,
+{
+    /// Opens the log in `path`, creating it if needed, and replays it in a new map.
+    pub fn open<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        Self::with_map(path, FixedSizeLruMap::with_capacity(capacity))
+    }
+}
+
+impl<K, V, S> WalCache<K, V, S>
+where
+    K: Clone + Eq + Hash + Persist,
+    V: Persist,
+    S: BuildHasher,
+{
+    /// Opens the log in `path`, creating it if needed, and replays it in `map`.
+    ///
+    /// A record torn by a crash at the end of the log is dropped.
+    pub fn with_map<P: AsRef<Path>>(path: P, map: FixedSizeLruMap<K, V, S>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)?;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        let (valid, records) = replay(&map, &bytes)?;
+
+        if valid < bytes.len() {
+            file.set_len(valid as u64)?;
+        }
+
+        Ok(WalCache {
+            compact_after: map.capacity().max(1) * 4,
+            log: Mutex::new(Log {
+                file: OpenOptions::new().append(true).open(&path)?,
+                records,
+            }),
+            map,
+            path,
+        })
+    }
+
+    /// Compacts the log once it holds more than `records`, four times the capacity by default.
+    pub fn compact_after(mut self, records: usize) -> Self {
+        self.compact_after = records;
+        self
+    }
+
+    /// Rewrites the log with only the current entries, from the least to the
+    /// most recently used, replacing the file atomically.
+    pub fn compact(&self) -> io::Result<()> {
+        self.compact_locked(&mut self.log.lock())
+    }
+
+    pub fn get(&self, key: &K) -> Option<MapGuard<V>> {
+        self.map.get(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) -> io::Result<Inserted<V>> {
+        let mut log = self.log.lock();
+        let mut record = vec![INSERT];
+        key.write_to(&mut record)?;
+        value.write_to(&mut record)?;
+        self.append(&mut log, &record)?;
+        Ok(self.map.insert(key, value))
+    }
+
+    /// Returns the underlying map. Mutations made directly on it skip the log.
+    pub fn map(&self) -> &FixedSizeLruMap<K, V, S> {
+        &self.map
+    }
+
+    pub fn remove(&self, key: &K) -> io::Result<Option<MapGuard<V>>> {
+        let mut log = self.log.lock();
+        let mut record = vec![REMOVE];
+        key.write_to(&mut record)?;
+        self.append(&mut log, &record)?;
+        Ok(self.map.remove(key))
+    }
+
+    /// Forces the log to disk, the records are otherwise only handed to the OS.
+    pub fn sync(&self) -> io::Result<()> {
+        self.log.lock().file.sync_data()
+    }
+
+    fn append(&self, log: &mut Log, record: &[u8]) -> io::Result<()> {
+        if log.records >= self.compact_after {
+            self.compact_locked(log)?;
+        }
+
+        log.file.write_all(record)?;
+        log.records += 1;
+        Ok(())
+    }
+
+    fn compact_locked(&self, log: &mut Log) -> io::Result<()> {
+        let entries = self.map.entries_by_recency();
+        let mut bytes = Vec::new();
+
+        for (k, v) in &entries {
+            bytes.push(INSERT);
+            k.write_to(&mut bytes)?;
+            v.write_to(&mut bytes)?;
+        }
+
+        replace_file(&self.path, |file| file.write_all(&bytes))?;
+
+        log.file = OpenOptions::new().append(true).open(&self.path)?;
+        log.records = entries.len();
+        Ok(())
+    }
+}
+
+/// Applies the records of `bytes` to `map`, returning the length of the valid
+/// records and their count.
+fn replay<K, V, S>(map: &FixedSizeLruMap<K, V, S>, bytes: &[u8]) -> io::Result<(usize, usize)>
+where
+    K: Clone + Eq + Hash + Persist,
+    V: Persist,
+    S: BuildHasher,
+{
+    let mut r = bytes;
+    let mut records = 0;
+
+    loop {
+        let valid = bytes.len() - r.len();
+
+        let record = match u8::read_from(&mut r) {
+            Ok(INSERT) => K::read_from(&mut r).and_then(|k| Ok((k, Some(V::read_from(&mut r)?)))),
+            Ok(REMOVE) => K::read_from(&mut r).map(|k| (k, None)),
+            Ok(_) => Err(io::Error::new(ErrorKind::InvalidData, "invalid log record")),
+            Err(e) => Err(e),
+        };
+
+        match record {
+            Ok((k, Some(v))) => drop(map.insert(k, v)),
+            Ok((k, None)) => drop(map.remove(&k)),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok((valid, records)),
+            Err(e) => return Err(e),
+        }
+
+        records += 1;
+    }
+}
+
+#[test]
+fn test_wal() {
+    let path = std::env::temp_dir().join(format!("fixed_size_lru_map_{}.wal", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let cache = WalCache::open(&path, 2).unwrap().compact_after(3);
+    cache.insert(1u8, 'a').unwrap();
+    cache.insert(2, 'b').unwrap();
+    cache.remove(&1).unwrap();
+    cache.insert(3, 'c').unwrap();
+    drop(cache);
+
+    // the compaction left the remaining entry, then came the last insert.
+    assert_eq!(2 * (1 + 1 + 4), std::fs::metadata(&path).unwrap().len());
+
+    // a torn record is dropped.
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[INSERT, 4]).unwrap();
+    drop(file);
+
+    let cache = WalCache::<u8, char>::open(&path, 2).unwrap();
+    let entries = cache.map().entries_by_recency();
+    assert_eq!(
+        vec![(2, 'b'), (3, 'c')],
+        entries.iter().map(|(k, v)| (*k, **v)).collect::<Vec<_>>()
+    );
+
+    cache.insert(4, 'd').unwrap();
+    drop(cache);
+
+    let cache = WalCache::<u8, char>::open(&path, 2).unwrap();
+    assert_eq!(Some('d'), cache.get(&4).map(|v| *v));
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,113 @@
+//! Per key subscriptions to the changes of a map.
+use crate::{events::Subscribers, CacheEvent, EventReceiver, FixedSizeLruMap};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash},
+    sync::atomic::{AtomicBool, Ordering::Relaxed},
+};
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Subscribes to the insertion, update and removal of `key`.
+    ///
+    /// The receiver can be awaited with [`recv`](EventReceiver::recv) or blocked
+    /// on with [`blocking_recv`](EventReceiver::blocking_recv). Watch before
+    /// looking the key up to never miss its arrival.
+    ///
+    /// # Example
+    /// ```
+    /// use fixed_size_lru_map::{CacheEvent, FixedSizeLruMap};
+    /// use std::{sync::Arc, thread};
+    ///
+    /// let map = Arc::new(FixedSizeLruMap::with_capacity(10));
+    /// let mut watch = map.watch(&"a");
+    ///
+    /// let writer = Arc::clone(&map);
+    /// thread::spawn(move || writer.insert("a", 1));
+    ///
+    /// if map.get(&"a").is_none() {
+    ///     let event = watch.blocking_recv().unwrap();
+    ///     assert!(matches!(event, CacheEvent::Insert("a", v) if *v == 1));
+    /// }
+    /// ```
+    pub fn watch(&self, key: &K) -> EventReceiver<CacheEvent<K, V>>
+    where
+        K: Clone,
+    {
+        self.watchers.subscribe(key.clone())
+    }
+}
+
+/// The subscribers of every watched key.
+pub(crate) struct Watchers<K, V> {
+    /// Whether any key is watched, so the writes take no lock otherwise.
+    any: AtomicBool,
+    keys: Mutex<HashMap<K, Subscribers<CacheEvent<K, V>>>>,
+}
+
+impl<K, V> Default for Watchers<K, V> {
+    fn default() -> Self {
+        Watchers {
+            any: AtomicBool::new(false),
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K, V> Watchers<K, V>
+where
+    K: Eq + Hash,
+{
+    pub fn is_empty(&self) -> bool {
+        !self.any.load(Relaxed)
+    }
+
+    /// Sends `event` to the watchers of its key.
+    pub fn send(&self, event: &CacheEvent<K, V>)
+    where
+        K: Clone,
+    {
+        let mut watchers = self.keys.lock();
+
+        if let Some(subscribers) = watchers.get(event.key()) {
+            subscribers.send(|| event.clone());
+
+            // sending forgets the dropped receivers.
+            if subscribers.is_empty() {
+                watchers.remove(event.key());
+                self.any.store(!watchers.is_empty(), Relaxed);
+            }
+        }
+    }
+
+    fn subscribe(&self, key: K) -> EventReceiver<CacheEvent<K, V>> {
+        let receiver = self.keys.lock().entry(key).or_default().subscribe();
+        self.any.store(true, Relaxed);
+        receiver
+    }
+}
+
+#[test]
+fn test_watch() {
+    let map = FixedSizeLruMap::with_capacity(1);
+    let mut watch = map.watch(&1);
+
+    map.insert(2, 'a');
+    map.insert(1, 'b');
+    map.insert(1, 'c');
+    map.insert(2, 'd');
+
+    let mut next = || watch.try_recv();
+    assert!(matches!(next(), Some(CacheEvent::Insert(1, v)) if *v == 'b'));
+    assert!(matches!(next(), Some(CacheEvent::Update { key: 1, .. })));
+    assert!(matches!(next(), Some(CacheEvent::Evict(1, v)) if *v == 'c'));
+    assert!(next().is_none());
+
+    drop(watch);
+    map.insert(1, 'e');
+    assert!(map.watchers.is_empty());
+}
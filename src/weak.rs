@@ -0,0 +1,145 @@
+//! A lru map holding its values weakly.
+use crate::{age, lock::Lock, MapGuard, WeakMapGuard};
+use std::{
+    borrow::Borrow,
+    collections::hash_map::{HashMap, RandomState},
+    hash::{BuildHasher, Hash},
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+};
+
+/// A fixed size lru map that does not keep its values alive.
+///
+/// A value stays reachable for as long as a [`MapGuard`] to it is held
+/// somewhere else and its key has not been evicted. This lets several owners
+/// share large buffers whose lifetime they control, without the map extending it.
+///
+/// # Example
+/// ```
+/// use fixed_size_lru_map::WeakLruMap;
+///
+/// let map = WeakLruMap::with_capacity(10);
+/// let buffer = map.insert("a", vec![0u8; 1024]);
+/// assert!(map.get(&"a").is_some());
+///
+/// drop(buffer);
+/// assert!(map.get(&"a").is_none());
+/// ```
+pub struct WeakLruMap<K, V, S = RandomState> {
+    age: AtomicU64,
+    capacity: usize,
+    map: Lock<HashMap<K, WeakMapGuard<V>, S>>,
+}
+
+impl<K, V> WeakLruMap<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K, V, S> WeakLruMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        WeakLruMap {
+            age: AtomicU64::new(0),
+            capacity,
+            map: Lock::new(HashMap::with_hasher(hash_builder)),
+        }
+    }
+
+    /// Returns the value of `key`, unless it was evicted or dropped by its owners.
+    pub fn get<Q>(&self, key: &Q) -> Option<MapGuard<V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let guard = self.map.read().get(key)?.upgrade()?;
+        guard.touch(&self.age);
+        Some(guard)
+    }
+
+    /// Inserts a value, returning the only guard keeping it alive.
+    pub fn insert(&self, key: K, value: V) -> MapGuard<V> {
+        let mut map = self.map.write();
+        let mut age = self.age.fetch_add(1, Relaxed);
+
+        if age >= age::REBASE_THRESHOLD {
+            let guards = map
+                .values()
+                .filter_map(WeakMapGuard::upgrade)
+                .collect::<Vec<_>>();
+            age::rebase(&self.age, guards.iter().map(|v| &v.0.age));
+            age = self.age.fetch_add(1, Relaxed);
+        }
+
+        let guard = MapGuard::new(age, value);
+        map.insert(key, MapGuard::downgrade(&guard));
+
+        if map.len() > self.capacity {
+            map.retain(|_, v| v.0.strong_count() > 0);
+        }
+
+        if map.len() > self.capacity {
+            let oldest = map
+                .iter()
+                .filter_map(|(k, v)| Some((k, v.upgrade()?.age())))
+                .min_by_key(|&(_, age)| age)
+                .map(|(k, _)| k.clone());
+
+            if let Some(key) = oldest {
+                map.remove(&key);
+            }
+        }
+
+        guard
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of keys, counting the values dropped since the last
+    /// [`purge`](Self::purge) or insertion that made room.
+    pub fn len(&self) -> usize {
+        self.map.read().len()
+    }
+
+    /// Forgets the keys whose value has been dropped.
+    pub fn purge(&self) {
+        self.map.write().retain(|_, v| v.0.strong_count() > 0);
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<MapGuard<V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.write().remove(key)?.upgrade()
+    }
+}
+
+#[test]
+fn test_weak_lru_map() {
+    let map = WeakLruMap::with_capacity(2);
+    let a = map.insert(1, "a");
+    let b = map.insert(2, "b");
+
+    // 2 is dropped so it makes room without evicting 1.
+    drop(b);
+    let _c = map.insert(3, "c");
+    assert_eq!("a", *map.get(&1).unwrap());
+
+    // both values are alive, 3 is the least recently used.
+    let _d = map.insert(4, "d");
+    assert!(map.get(&3).is_none());
+    assert_eq!(2, map.len());
+
+    drop(a);
+    map.purge();
+    assert_eq!(1, map.len());
+}
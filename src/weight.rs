@@ -0,0 +1,130 @@
+//! Weighing of the entries to estimate the size of a map.
+use crate::{Entry, FixedSizeLruMap, MapGuard};
+use std::{
+    hash::{BuildHasher, Hash},
+    mem::size_of,
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+};
+
+pub(crate) type Weigher<K, V> = Box<dyn Fn(&K, &V) -> u64 + Send + Sync>;
+
+impl<K, V, S> FixedSizeLruMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Returns the sum of the weights of the entries, 0 without a
+    /// [`weigher`](crate::FixedSizeLruMapBuilder::weigher).
+    pub fn weighted_size(&self) -> u64 {
+        self.weights.total.load(Relaxed)
+    }
+
+    /// Returns an approximation of the memory used by the map, in bytes.
+    ///
+    /// This counts the table and the entry allocations, plus the
+    /// [`weighted_size`](Self::weighted_size) taken as the heap bytes owned by
+    /// the keys and values.
+    pub fn estimated_bytes(&self) -> u64 {
+        let (len, buckets) = {
+            let map = self.map.read();
+            (map.len(), map.capacity())
+        };
+
+        // a bucket holds the key, the guard and a control byte; an entry
+        // allocation holds the two reference counts and the entry itself.
+        let bucket = size_of::<K>() + size_of::<MapGuard<V>>() + 1;
+        let entry = 2 * size_of::<usize>() + size_of::<Entry<V>>();
+
+        (size_of::<Self>() + buckets * bucket + len * entry) as u64 + self.weighted_size()
+    }
+}
+
+/// The total weight of the entries of a map.
+pub(crate) struct Weights<K, V> {
+    pub max: Option<u64>,
+    total: AtomicU64,
+    pub weigher: Option<Weigher<K, V>>,
+}
+
+impl<K, V> Default for Weights<K, V> {
+    fn default() -> Self {
+        Weights {
+            max: None,
+            total: AtomicU64::new(0),
+            weigher: None,
+        }
+    }
+}
+
+impl<K, V> Weights<K, V> {
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.weigher.is_some()
+    }
+
+    /// Returns whether the entries weigh more than the [`max_weight`](crate::FixedSizeLruMapBuilder::max_weight).
+    #[inline]
+    pub fn over_budget(&self) -> bool {
+        self.max.is_some_and(|max| self.total.load(Relaxed) > max)
+    }
+
+    pub fn add(&self, key: &K, value: &V) {
+        if let Some(weigher) = &self.weigher {
+            self.total.fetch_add(weigher(key, value), Relaxed);
+        }
+    }
+
+    pub fn sub(&self, key: &K, value: &V) {
+        if let Some(weigher) = &self.weigher {
+            let w = weigher(key, value);
+            let _ = self
+                .total
+                .fetch_update(Relaxed, Relaxed, |t| Some(t.saturating_sub(w)));
+        }
+    }
+}
+
+#[test]
+fn test_weighted_size() {
+    let map = FixedSizeLruMap::builder(2)
+        .weigher(|k: &&str, v: &String| (k.len() + v.len()) as u64)
+        .build();
+
+    map.insert("a", "xx".to_owned());
+    map.insert("b", "yyy".to_owned());
+    assert_eq!(7, map.weighted_size());
+
+    map.insert("a", "z".to_owned());
+    assert_eq!(6, map.weighted_size());
+
+    // "b" is evicted.
+    map.insert("c", "".to_owned());
+    assert_eq!(3, map.weighted_size());
+
+    map.remove(&"a");
+    assert_eq!(1, map.weighted_size());
+    assert!(map.estimated_bytes() > 1);
+}
+
+#[test]
+fn test_max_weight() {
+    let map = FixedSizeLruMap::builder(100)
+        .weigher(|_: &u32, v: &Vec<u8>| v.len() as u64)
+        .max_weight(10)
+        .build();
+
+    map.insert(1, vec![0; 4]);
+    map.insert(2, vec![0; 4]);
+    map.insert(3, vec![0; 1]);
+    assert_eq!(9, map.weighted_size());
+
+    // 1 and 2 are evicted to fit, only the first is returned.
+    let (_, evicted) = map.insert(4, vec![0; 8]);
+    assert_eq!(4, evicted.unwrap().len());
+    assert_eq!(9, map.weighted_size());
+    assert_eq!(2, map.len());
+
+    // an entry heavier than the budget stays alone.
+    map.insert(5, vec![0; 20]);
+    assert_eq!(vec![5], map.view().keys().copied().collect::<Vec<_>>());
+}